@@ -1,22 +1,54 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
     middleware,
-    response::Json,
-    routing::{get, post},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+    routing::{delete, get, post, put},
     Router,
 };
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::sync::Arc;
 
-use crate::auth::{auth_middleware, AuthState};
-use crate::notify::{NotifyRequest, Notifier};
-use crate::scheduler::{NotificationScheduler, OneTimeRequest, ScheduleRequest};
+use crate::audit::{audit_middleware, AuditEntry, AuditLog, AuditQuery};
+use crate::auth::{auth_middleware, require_scope, AuthState};
+use crate::bulk::{self, BulkItemResult, BulkRequest};
+use crate::callback::{CallbackDispatcher, DeliveryRecord};
+use crate::describe::{describe, Description};
+use crate::events::{EventBus, EventFilter, EventKind};
+use crate::health::{Readiness, ReadyStatus};
+use crate::idempotency::{idempotency_middleware, IdempotencyStore};
+use crate::metrics::{self, METRICS};
+use crate::natural::{self, ParsedTime};
+use crate::network::{network_middleware, NetworkPolicy};
+use crate::notify::{NotifyRequest, Notifier, DEFAULT_DEVICE};
+use crate::ratelimit::{rate_limit_middleware, RateLimiter};
+use crate::recurrence::normalize_cron;
+use crate::scheduler::{
+    parse_schedule, JobFilter, JobKind, JobQuery, JobStatus, NotificationScheduler,
+    OneTimeRequest, ScheduleRequest, ScheduleSpec, SortOrder,
+};
+use crate::template::{NamedTemplate, NotifyTemplate, TemplateRequest, TemplateStore};
+use crate::tokens::{ApiToken, IssuedToken, Principal, Scope, TokenRegistry, TokenRequest};
 
 #[derive(Clone)]
 pub struct AppState {
     pub notifier: Arc<Notifier>,
     pub scheduler: Arc<NotificationScheduler>,
+    pub templates: Arc<TemplateStore>,
+    pub idempotency: Arc<IdempotencyStore>,
+    pub events: Arc<EventBus>,
+    pub callbacks: Arc<CallbackDispatcher>,
+    pub readiness: Arc<Readiness>,
+    pub tokens: Arc<TokenRegistry>,
+    pub limiter: Arc<RateLimiter>,
+    pub audit: Arc<AuditLog>,
+    pub network: Arc<NetworkPolicy>,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,6 +69,72 @@ pub struct NotifyResponse {
 #[derive(Debug, Serialize)]
 pub struct JobCreatedResponse {
     pub job_id: String,
+    // 统一为 6 位格式后的 cron 表达式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+}
+
+// 预览默认返回的触发次数和上限
+const DEFAULT_PREVIEW_COUNT: usize = 5;
+const MAX_PREVIEW_COUNT: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    #[serde(flatten)]
+    pub schedule: ScheduleSpec,
+    pub start_at: Option<DateTime<Utc>>,
+    // IANA 时区名，如 "Asia/Shanghai"，用于同时返回本地时间
+    pub timezone: Option<String>,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewResponse {
+    #[serde(rename = "type")]
+    pub kind: JobKind,
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    pub next_runs: Vec<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_runs_local: Option<Vec<String>>,
+    pub description: Description,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    #[serde(flatten)]
+    pub filter: EventFilter,
+    // EventSource 无法自定义请求头时可用查询参数代替 Last-Event-ID
+    pub last_event_id: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParseTimeRequest {
+    pub text: String,
+    // IANA 时区名，默认 UTC
+    pub timezone: Option<String>,
+    // 解析相对时间的参照时间，默认当前时间
+    pub reference: Option<DateTime<Utc>>,
+}
+
+// 下一页游标通过响应头返回，保持 data 仍为任务数组
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+#[derive(Debug, Deserialize)]
+pub struct ListJobsQuery {
+    pub tag: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<JobKind>,
+    pub status: Option<JobStatus>,
+    pub group: Option<String>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub created_after: Option<DateTime<Utc>>,
+    // 按创建任务的令牌筛选，仅 admin 有效
+    pub owner: Option<String>,
+    pub sort: Option<SortOrder>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
 }
 
 impl<T> ApiResponse<T> {
@@ -60,27 +158,94 @@ impl<T> ApiResponse<T> {
 // Handler for immediate notification
 async fn notify_now(
     State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
     Json(req): Json<NotifyRequest>,
-) -> Result<Json<ApiResponse<NotifyResponse>>, StatusCode> {
+) -> Result<Json<ApiResponse<NotifyResponse>>, Response> {
+    let req = match state.templates.resolve(req).await {
+        Ok(req) => req,
+        Err(e) => return Ok(Json(ApiResponse::error(e.to_string()))),
+    };
+    let device = req.device.as_deref();
+    if let Err(e) = principal
+        .check_device(device)
+        .and_then(|_| state.notifier.check_device(device))
+    {
+        return Ok(Json(ApiResponse::error(e.to_string())));
+    }
+    // 超出设备推送频率时返回 429
+    if let Some(rejected) = state.limiter.check_device(device) {
+        return Err(rejected);
+    }
+
     match state.notifier.send(&req).await {
-        Ok(resp) => Ok(Json(ApiResponse::success(NotifyResponse {
-            code: resp.code,
-            message: resp.message,
-        }))),
+        Ok(resp) => {
+            state.events.publish(
+                EventKind::DeliverySucceeded,
+                None,
+                Some(&principal.id),
+                &[],
+                serde_json::json!({ "code": resp.code, "message": resp.message }),
+            );
+            Ok(Json(ApiResponse::success(NotifyResponse {
+                code: resp.code,
+                message: resp.message,
+            })))
+        }
         Err(e) => {
             tracing::error!("Failed to send notification: {}", e);
+            state.events.publish(
+                EventKind::DeliveryFailed,
+                None,
+                Some(&principal.id),
+                &[],
+                serde_json::json!({ "error": e.to_string() }),
+            );
             Ok(Json(ApiResponse::error(e.to_string())))
         }
     }
 }
 
+// Handler for streaming job and delivery events (Server-Sent Events)
+async fn stream_events(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(mut query): Query<EventsQuery>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let last_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .or(query.last_event_id);
+    // 非 admin 只能收到自己的任务和推送事件
+    if let Some(owner) = principal.owner_filter() {
+        query.filter.owner = Some(owner);
+    }
+    Sse::new(state.events.stream(query.filter, last_id)).keep_alive(KeepAlive::default())
+}
+
 // Handler for scheduling a cron job
 async fn schedule_cron(
     State(state): State<AppState>,
-    Json(req): Json<ScheduleRequest>,
+    Extension(principal): Extension<Principal>,
+    Json(mut req): Json<ScheduleRequest>,
 ) -> Result<Json<ApiResponse<JobCreatedResponse>>, StatusCode> {
-    match state.scheduler.add_cron_job(req).await {
-        Ok(job_id) => Ok(Json(ApiResponse::success(JobCreatedResponse { job_id }))),
+    req.notify = match state.templates.resolve(req.notify).await {
+        Ok(notify) => notify,
+        Err(e) => return Ok(Json(ApiResponse::error(e.to_string()))),
+    };
+    if let Err(e) = principal.check_device(req.notify.device.as_deref()) {
+        return Ok(Json(ApiResponse::error(e.to_string())));
+    }
+
+    let quota = state.limiter.job_quota(&principal);
+    if let Err(e) = state.scheduler.check_job_quota(&principal.id, quota, 1).await {
+        return Ok(Json(ApiResponse::error(e.to_string())));
+    }
+
+    let cron = req.schedule.cron.as_deref().and_then(|cron| normalize_cron(cron).ok());
+    match state.scheduler.add_cron_job(req, &principal.id, quota).await {
+        Ok(job_id) => Ok(Json(ApiResponse::success(JobCreatedResponse { job_id, cron }))),
         Err(e) => {
             tracing::error!("Failed to schedule cron job: {}", e);
             Ok(Json(ApiResponse::error(e.to_string())))
@@ -88,13 +253,82 @@ async fn schedule_cron(
     }
 }
 
+// Handler for previewing a schedule without creating a job
+async fn preview_schedule(
+    Json(req): Json<PreviewRequest>,
+) -> Result<Json<ApiResponse<PreviewResponse>>, StatusCode> {
+    let timezone = match req.timezone.as_deref().map(|name| natural::parse_timezone(Some(name))) {
+        None => None,
+        Some(Ok(tz)) => Some(tz),
+        Some(Err(e)) => return Ok(Json(ApiResponse::error(e.to_string()))),
+    };
+    let count = req.count.unwrap_or(DEFAULT_PREVIEW_COUNT);
+    if count == 0 || count > MAX_PREVIEW_COUNT {
+        return Ok(Json(ApiResponse::error(format!(
+            "count must be between 1 and {}",
+            MAX_PREVIEW_COUNT
+        ))));
+    }
+
+    let now = Utc::now();
+    let schedule = match parse_schedule(&req.schedule, req.start_at, now) {
+        Ok(schedule) => schedule,
+        Err(e) => return Ok(Json(ApiResponse::error(e.to_string()))),
+    };
+    let after = req.start_at.map_or(now, |start| {
+        start.max(now) - chrono::Duration::milliseconds(1)
+    });
+    let next_runs = schedule.upcoming(after, count);
+    let next_runs_local = timezone.map(|tz| {
+        next_runs
+            .iter()
+            .map(|run| run.with_timezone(&tz).to_rfc3339())
+            .collect()
+    });
+
+    Ok(Json(ApiResponse::success(PreviewResponse {
+        kind: schedule.kind(),
+        expression: schedule.expression().to_string(),
+        timezone: timezone.map(|tz| tz.name().to_string()),
+        next_runs,
+        next_runs_local,
+        description: describe(&schedule),
+    })))
+}
+
+// Handler for parsing a natural-language time phrase
+async fn parse_time(
+    Json(req): Json<ParseTimeRequest>,
+) -> Result<Json<ApiResponse<ParsedTime>>, StatusCode> {
+    let parsed = natural::parse_timezone(req.timezone.as_deref()).and_then(|tz| {
+        natural::parse(&req.text, req.reference.unwrap_or_else(Utc::now), tz)
+    });
+    match parsed {
+        Ok(parsed) => Ok(Json(ApiResponse::success(parsed))),
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
 // Handler for scheduling a one-time job
 async fn schedule_one_time(
     State(state): State<AppState>,
-    Json(req): Json<OneTimeRequest>,
+    Extension(principal): Extension<Principal>,
+    Json(mut req): Json<OneTimeRequest>,
 ) -> Result<Json<ApiResponse<JobCreatedResponse>>, StatusCode> {
-    match state.scheduler.add_one_time_job(req).await {
-        Ok(job_id) => Ok(Json(ApiResponse::success(JobCreatedResponse { job_id }))),
+    req.notify = match state.templates.resolve(req.notify).await {
+        Ok(notify) => notify,
+        Err(e) => return Ok(Json(ApiResponse::error(e.to_string()))),
+    };
+    if let Err(e) = principal.check_device(req.notify.device.as_deref()) {
+        return Ok(Json(ApiResponse::error(e.to_string())));
+    }
+    let quota = state.limiter.job_quota(&principal);
+    if let Err(e) = state.scheduler.check_job_quota(&principal.id, quota, 1).await {
+        return Ok(Json(ApiResponse::error(e.to_string())));
+    }
+
+    match state.scheduler.add_one_time_job(req, &principal.id, quota).await {
+        Ok(job_id) => Ok(Json(ApiResponse::success(JobCreatedResponse { job_id, cron: None }))),
         Err(e) => {
             tracing::error!("Failed to schedule one-time job: {}", e);
             Ok(Json(ApiResponse::error(e.to_string())))
@@ -102,52 +336,284 @@ async fn schedule_one_time(
     }
 }
 
-// Handler for listing all jobs
+// Handler for listing jobs, with optional filters and pagination
 async fn list_jobs(
     State(state): State<AppState>,
-) -> Result<Json<ApiResponse<Vec<crate::scheduler::ScheduledJob>>>, StatusCode> {
-    let jobs = state.scheduler.list_jobs().await;
-    Ok(Json(ApiResponse::success(jobs)))
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<ListJobsQuery>,
+) -> Result<(HeaderMap, Json<ApiResponse<Vec<crate::scheduler::ScheduledJob>>>), StatusCode> {
+    let query = JobQuery {
+        filter: JobFilter {
+            tag: query.tag,
+            kind: query.kind,
+            status: query.status,
+            group: query.group,
+            created_before: query.created_before,
+            created_after: query.created_after,
+            // 非 admin 只能查看自己的任务
+            owner: principal.owner_filter().or(query.owner),
+        },
+        sort: query.sort.unwrap_or_default(),
+        cursor: query.cursor,
+        limit: query.limit,
+    };
+    let page = state.scheduler.query_jobs(&query).await;
+
+    let mut headers = HeaderMap::new();
+    if let Some(cursor) = page.next_cursor
+        && let Ok(value) = HeaderValue::from_str(&cursor)
+    {
+        headers.insert(NEXT_CURSOR_HEADER, value);
+    }
+    Ok((headers, Json(ApiResponse::success(page.jobs))))
 }
 
 // Handler for getting a specific job
 async fn get_job(
     State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
     Path(job_id): Path<String>,
 ) -> Result<Json<ApiResponse<crate::scheduler::ScheduledJob>>, StatusCode> {
+    // 其他令牌的任务按不存在处理
     match state.scheduler.get_job(&job_id).await {
-        Some(job) => Ok(Json(ApiResponse::success(job))),
-        None => Ok(Json(ApiResponse::error("Job not found"))),
+        Some(job) if principal.owns(&job.owner) => Ok(Json(ApiResponse::success(job))),
+        _ => Ok(Json(ApiResponse::error("Job not found"))),
+    }
+}
+
+// Handler for listing callback delivery attempts of a job
+async fn job_callbacks(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(job_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<DeliveryRecord>>>, StatusCode> {
+    match state.callbacks.log(&job_id).await {
+        Some((owner, records)) if principal.owns(&owner) => Ok(Json(ApiResponse::success(records))),
+        _ => Ok(Json(ApiResponse::error("No callback registered for this job"))),
     }
 }
 
 // Handler for removing a job
 async fn remove_job(
     State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
     Path(job_id): Path<String>,
 ) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    if !state
+        .scheduler
+        .get_job(&job_id)
+        .await
+        .is_some_and(|job| principal.owns(&job.owner))
+    {
+        return Ok(Json(ApiResponse::error(format!("Job {} not found", job_id))));
+    }
     match state.scheduler.remove_job(&job_id).await {
         Ok(_) => Ok(Json(ApiResponse::success(()))),
         Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
     }
 }
 
+// Handler for bulk job operations
+async fn bulk_jobs(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<BulkRequest>,
+) -> Result<Json<ApiResponse<Vec<BulkItemResult>>>, StatusCode> {
+    let quota = state.limiter.job_quota(&principal);
+    match bulk::run(&state.scheduler, &state.templates, &principal, quota, req).await {
+        Ok(outcome) => Ok(Json(ApiResponse {
+            success: outcome.error.is_none(),
+            data: Some(outcome.results),
+            error: outcome.error,
+        })),
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// Handler for creating an API token; the token value is only returned here
+async fn create_token(
+    State(state): State<AppState>,
+    Json(req): Json<TokenRequest>,
+) -> Result<Json<ApiResponse<IssuedToken>>, StatusCode> {
+    for device in &req.devices {
+        if let Err(e) = state.notifier.check_device(Some(device)) {
+            return Ok(Json(ApiResponse::error(e.to_string())));
+        }
+    }
+    match state.tokens.create(req).await {
+        Ok(issued) => {
+            tracing::info!("Created API token {} ({})", issued.info.name, issued.info.id);
+            Ok(Json(ApiResponse::success(issued)))
+        }
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// Handler for querying the audit log, newest first
+async fn query_audit(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Json<ApiResponse<Vec<AuditEntry>>> {
+    let audit = Arc::clone(&state.audit);
+    // 读取日志文件是阻塞操作
+    let result = tokio::task::spawn_blocking(move || audit.query(&query))
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e)));
+    match result {
+        Ok(entries) => Json(ApiResponse::success(entries)),
+        Err(e) => Json(ApiResponse::error(e.to_string())),
+    }
+}
+
+// Handler for listing API tokens (without token values)
+async fn list_tokens(State(state): State<AppState>) -> Json<ApiResponse<Vec<ApiToken>>> {
+    Json(ApiResponse::success(state.tokens.list().await))
+}
+
+// Handler for revoking an API token
+async fn remove_token(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match state.tokens.remove(&id).await {
+        Ok(_) => {
+            tracing::info!("Revoked API token {}", id);
+            Ok(Json(ApiResponse::success(())))
+        }
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// Handler for rotating an API token; the old value stops working immediately
+async fn rotate_token(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<IssuedToken>>, StatusCode> {
+    match state.tokens.rotate(&id).await {
+        Ok(issued) => {
+            tracing::info!("Rotated API token {} ({})", issued.info.name, id);
+            Ok(Json(ApiResponse::success(issued)))
+        }
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// Handler for creating a notification template
+async fn create_template(
+    State(state): State<AppState>,
+    Json(req): Json<TemplateRequest>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match state.templates.create(req).await {
+        Ok(_) => Ok(Json(ApiResponse::success(()))),
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// Handler for listing all templates
+async fn list_templates(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<NamedTemplate>>>, StatusCode> {
+    Ok(Json(ApiResponse::success(state.templates.list().await)))
+}
+
+// Handler for getting a specific template
+async fn get_template(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<NamedTemplate>>, StatusCode> {
+    match state.templates.get(&name).await {
+        Some(template) => Ok(Json(ApiResponse::success(NamedTemplate { name, template }))),
+        None => Ok(Json(ApiResponse::error("Template not found"))),
+    }
+}
+
+// Handler for replacing a template
+async fn update_template(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(template): Json<NotifyTemplate>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match state.templates.update(&name, template).await {
+        Ok(_) => Ok(Json(ApiResponse::success(()))),
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
+// Handler for removing a template
+async fn remove_template(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match state.templates.remove(&name).await {
+        Ok(_) => Ok(Json(ApiResponse::success(()))),
+        Err(e) => Ok(Json(ApiResponse::error(e.to_string()))),
+    }
+}
+
 // Health check
 async fn health() -> &'static str {
     "OK"
 }
 
+// 就绪检查：调度器、任务列表、Bark 可达性，异常时返回 503
+async fn ready(State(state): State<AppState>) -> impl IntoResponse {
+    let report = state
+        .readiness
+        .check(&state.scheduler, &state.notifier)
+        .await;
+    let status = match report.status {
+        ReadyStatus::Ready => StatusCode::OK,
+        ReadyStatus::Degraded => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report))
+}
+
+// Prometheus 指标（文本格式）
+async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let active = state.scheduler.active_counts().await;
+    let active: Vec<(&str, usize)> = active
+        .into_iter()
+        .map(|(kind, count)| (kind.as_str(), count))
+        .collect();
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        METRICS.render(&active),
+    )
+}
+
 // Get device key info (without exposing the full key)
-async fn device_info(State(state): State<AppState>) -> Json<serde_json::Value> {
-    let key = &state.notifier.device_key;
-    let masked = if key.len() > 8 {
-        format!("{}...{}", &key[..4], &key[key.len()-4..])
-    } else {
-        "***".to_string()
+async fn device_info(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Json<serde_json::Value> {
+    let mask = |key: &str| {
+        if key.len() > 8 {
+            format!("{}...{}", &key[..4], &key[key.len()-4..])
+        } else {
+            "***".to_string()
+        }
     };
-    
+
+    // 只列出当前令牌可以推送的设备
+    let devices: Vec<serde_json::Value> = state
+        .notifier
+        .devices
+        .iter()
+        .filter(|(name, _)| principal.check_device(Some(name)).is_ok())
+        .map(|(name, key)| serde_json::json!({ "name": name, "device_key": mask(key) }))
+        .collect();
+    let default_key = state
+        .notifier
+        .devices
+        .get(DEFAULT_DEVICE)
+        .filter(|_| principal.check_device(None).is_ok());
+
     Json(serde_json::json!({
-        "device_key": masked,
+        "device_key": default_key.map(|key| mask(key)),
+        "devices": devices,
         "status": "active"
     }))
 }
@@ -156,22 +622,82 @@ pub fn create_router(state: AppState, auth_state: AuthState) -> Router {
     // 公开路由（不需要认证）
     let public_routes = Router::new()
         .route("/", get(|| async { "Agent Bark API" }))
-        .route("/health", get(health));
+        .route("/health", get(health))
+        .route("/ready", get(ready));
+
+    // 按路由要求的权限范围
+    let scope = |scope: Scope| middleware::from_fn_with_state(scope, require_scope);
+
+    // 支持幂等键的路由
+    let idempotent_routes = Router::new()
+        // Immediate notification
+        .route("/notify", post(notify_now).route_layer(scope(Scope::Notify)))
+        // Scheduled notifications
+        .route("/schedule/cron", post(schedule_cron).route_layer(scope(Scope::Schedule)))
+        .route("/schedule/once", post(schedule_one_time).route_layer(scope(Scope::Schedule)))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state.idempotency),
+            idempotency_middleware,
+        ));
 
     // 需要认证的路由
     let protected_routes = Router::new()
         .route("/device", get(device_info))
-        // Immediate notification
-        .route("/notify", post(notify_now))
-        // Scheduled notifications
-        .route("/schedule/cron", post(schedule_cron))
-        .route("/schedule/once", post(schedule_one_time))
+        .route("/metrics", get(prometheus_metrics).route_layer(scope(Scope::Admin)))
+        .merge(idempotent_routes)
+        .route("/schedule/preview", post(preview_schedule).route_layer(scope(Scope::Schedule)))
+        .route("/parse-time", post(parse_time).route_layer(scope(Scope::Schedule)))
+        .route("/events", get(stream_events).route_layer(scope(Scope::JobsRead)))
         // Job management
-        .route("/jobs", get(list_jobs))
-        .route("/jobs/:job_id", get(get_job).delete(remove_job))
+        .route("/jobs", get(list_jobs).route_layer(scope(Scope::JobsRead)))
+        .route("/jobs/bulk", post(bulk_jobs).route_layer(scope(Scope::JobsWrite)))
+        .route("/jobs/:job_id", get(get_job).route_layer(scope(Scope::JobsRead)))
+        .route("/jobs/:job_id", delete(remove_job).route_layer(scope(Scope::JobsWrite)))
+        .route(
+            "/jobs/:job_id/callbacks",
+            get(job_callbacks).route_layer(scope(Scope::JobsRead)),
+        )
+        // Notification templates: 所有令牌可读，修改需要 admin
+        .route("/templates", get(list_templates))
+        .route("/templates", post(create_template).route_layer(scope(Scope::Admin)))
+        .route("/templates/:name", get(get_template))
+        .route(
+            "/templates/:name",
+            put(update_template)
+                .delete(remove_template)
+                .route_layer(scope(Scope::Admin)),
+        )
+        // Token management
+        .route(
+            "/admin/tokens",
+            get(list_tokens)
+                .post(create_token)
+                .route_layer(scope(Scope::Admin)),
+        )
+        .route("/admin/tokens/:id", delete(remove_token).route_layer(scope(Scope::Admin)))
+        .route(
+            "/admin/tokens/:id/rotate",
+            post(rotate_token).route_layer(scope(Scope::Admin)),
+        )
+        .route("/admin/audit", get(query_audit).route_layer(scope(Scope::Admin)))
+        // 限流和审计依赖认证得到的调用方，需在 auth_middleware 之后执行；被限流的请求也记入审计
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state.limiter),
+            rate_limit_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state.audit),
+            audit_middleware,
+        ))
         .layer(middleware::from_fn_with_state(auth_state, auth_middleware));
 
+    // 来源网段检查在认证之前，对公开路由同样生效
     public_routes
         .merge(protected_routes)
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state.network),
+            network_middleware,
+        ))
+        .layer(middleware::from_fn(metrics::track_http))
         .with_state(state)
 }
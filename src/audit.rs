@@ -0,0 +1,369 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{MatchedPath, RawPathParams, Request, State},
+    http::{HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    RequestExt,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::client_ip;
+use crate::tokens::Principal;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// 查询默认返回的条数和上限
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+// 记录的错误信息最大长度
+const MAX_ERROR_LEN: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Success,
+    // 请求已处理但失败（success: false 或服务端错误）
+    Failure,
+    // 缺少权限范围或被限流
+    Denied,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub request_id: String,
+    pub principal: String,
+    pub principal_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    // 操作的任务、令牌 ID 或模板名称：路径参数，或创建任务、令牌成功后返回的 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub status: u16,
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    // 调用方令牌 ID
+    pub principal: Option<String>,
+    pub action: Option<String>,
+    pub outcome: Option<Outcome>,
+    pub target: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.principal.as_ref().is_none_or(|p| *p == entry.principal)
+            && self.action.as_ref().is_none_or(|a| *a == entry.action)
+            && self.outcome.is_none_or(|o| o == entry.outcome)
+            && self.target.as_ref().is_none_or(|t| entry.target.as_ref() == Some(t))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+    }
+}
+
+// 等待写入的记录数上限，写入跟不上时请求等待而不是丢弃记录
+const QUEUE_CAPACITY: usize = 1024;
+
+struct AuditFile {
+    file: File,
+    size: u64,
+}
+
+/// 审计日志：追加写入 JSON Lines 文件，超过 max_bytes 时轮换为 .1、.2 …，保留 max_files 个旧文件
+pub struct AuditLog {
+    path: PathBuf,
+    max_files: usize,
+    // 为 None 时不记录
+    sender: Option<mpsc::Sender<AuditEntry>>,
+}
+
+impl AuditLog {
+    /// path 为空时不记录；写入和轮换在独立的阻塞线程中进行，需在 tokio 运行时内调用
+    pub fn new(path: &str, max_bytes: u64, max_files: usize) -> anyhow::Result<Self> {
+        let path = PathBuf::from(path);
+        if path.as_os_str().is_empty() {
+            return Ok(Self {
+                path,
+                max_files,
+                sender: None,
+            });
+        }
+        let file = open(&path)
+            .map_err(|e| anyhow::anyhow!("打开审计日志 {} 失败: {}", path.display(), e))?;
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let writer = AuditWriter {
+            path: path.clone(),
+            max_bytes,
+            max_files,
+            current: file,
+        };
+        tokio::task::spawn_blocking(move || writer.run(receiver));
+        Ok(Self {
+            path,
+            max_files,
+            sender: Some(sender),
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    async fn record(&self, entry: AuditEntry) {
+        if let Some(sender) = &self.sender
+            && sender.send(entry).await.is_err()
+        {
+            error!("Audit log writer stopped, entry dropped");
+        }
+    }
+
+    /// 从新到旧查询，包括已轮换的文件
+    pub fn query(&self, query: &AuditQuery) -> anyhow::Result<Vec<AuditEntry>> {
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        if limit == 0 || limit > MAX_QUERY_LIMIT {
+            return Err(anyhow::anyhow!("limit must be between 1 and {}", MAX_QUERY_LIMIT));
+        }
+        if !self.enabled() {
+            return Err(anyhow::anyhow!("Audit log is disabled"));
+        }
+
+        // 不与写入线程争锁；查询期间恰好发生轮换时可能漏读或重复少量记录
+        let mut results = Vec::new();
+        let files = std::iter::once(self.path.clone())
+            .chain((1..=self.max_files).map(|index| rotated(&self.path, index)));
+        for path in files {
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let mut entries: Vec<AuditEntry> = BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str(&line).ok())
+                .filter(|entry| query.matches(entry))
+                .collect();
+            entries.reverse();
+            results.extend(entries);
+            if results.len() >= limit {
+                break;
+            }
+        }
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+/// 独占审计文件的写入线程
+struct AuditWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    current: AuditFile,
+}
+
+impl AuditWriter {
+    fn run(mut self, mut receiver: mpsc::Receiver<AuditEntry>) {
+        while let Some(entry) = receiver.blocking_recv() {
+            self.write(&entry);
+        }
+    }
+
+    fn write(&mut self, entry: &AuditEntry) {
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Failed to serialize audit entry: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let size = self.current.size;
+        if self.max_bytes > 0 && size > 0 && size + line.len() as u64 > self.max_bytes {
+            match self.rotate() {
+                Ok(file) => self.current = file,
+                Err(e) => error!("Failed to rotate audit log {}: {}", self.path.display(), e),
+            }
+        }
+        match self.current.file.write_all(&line) {
+            Ok(()) => self.current.size += line.len() as u64,
+            Err(e) => error!("Failed to write audit log {}: {}", self.path.display(), e),
+        }
+    }
+
+    /// audit.jsonl -> audit.jsonl.1 -> audit.jsonl.2 …，超出 max_files 的删除
+    fn rotate(&self) -> std::io::Result<AuditFile> {
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(rotated(&self.path, self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = rotated(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated(&self.path, 1))?;
+        }
+        open(&self.path)
+    }
+}
+
+fn rotated(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.to_path_buf().into_os_string();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+fn open(path: &Path) -> std::io::Result<AuditFile> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    Ok(AuditFile { file, size })
+}
+
+/// 需要审计的接口及其操作名
+fn action(method: &Method, route: &str) -> Option<&'static str> {
+    let action = match (method.as_str(), route) {
+        ("POST", "/notify") => "notify",
+        ("POST", "/schedule/cron") => "schedule_cron",
+        ("POST", "/schedule/once") => "schedule_once",
+        ("DELETE", "/jobs/:job_id") => "remove_job",
+        ("POST", "/jobs/bulk") => "bulk_jobs",
+        ("GET", "/admin/tokens") => "list_tokens",
+        ("POST", "/admin/tokens") => "create_token",
+        ("DELETE", "/admin/tokens/:id") => "remove_token",
+        ("POST", "/admin/tokens/:id/rotate") => "rotate_token",
+        ("GET", "/admin/audit") => "query_audit",
+        ("POST", "/templates") => "create_template",
+        ("PUT", "/templates/:name") => "update_template",
+        ("DELETE", "/templates/:name") => "remove_template",
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// 沿用客户端传入的 X-Request-Id（长度合理的可见字符），否则生成新的
+fn request_id(request: &Request) -> String {
+    request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_graphic()))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string)
+}
+
+/// 为请求分配 X-Request-Id，并记录需要审计的接口；需在 auth_middleware 之后执行
+pub async fn audit_middleware(
+    State(audit): State<Arc<AuditLog>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let request_id = request_id(&request);
+    let action = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|route| action(request.method(), route.as_str()));
+    let (Some(action), true) = (action, audit.enabled()) else {
+        return with_request_id(next.run(request).await, &request_id);
+    };
+
+    let principal = request
+        .extensions()
+        .get::<Principal>()
+        .cloned()
+        .unwrap_or_else(Principal::anonymous);
+    let ip = client_ip(&request).map(|ip| ip.to_string());
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let mut target = request
+        .extract_parts::<RawPathParams>()
+        .await
+        .ok()
+        .and_then(|params| params.iter().next().map(|(_, value)| value.to_string()));
+
+    let response = next.run(request).await;
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    // 处理器以 200 + success: false 返回业务错误，需要读取响应体判断结果
+    let json: Option<serde_json::Value> = serde_json::from_slice(&bytes).ok();
+    let succeeded = json
+        .as_ref()
+        .and_then(|json| json.get("success"))
+        .and_then(|success| success.as_bool())
+        .unwrap_or(true);
+    let status = parts.status;
+    let outcome = if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+        Outcome::Denied
+    } else if status.is_success() && succeeded {
+        Outcome::Success
+    } else {
+        Outcome::Failure
+    };
+    let error = match outcome {
+        Outcome::Success => None,
+        _ => Some(
+            json.as_ref()
+                .and_then(|json| json.get("error"))
+                .and_then(|error| error.as_str())
+                .map_or_else(|| String::from_utf8_lossy(&bytes).into_owned(), str::to_string)
+                .chars()
+                .take(MAX_ERROR_LEN)
+                .collect(),
+        ),
+    };
+    if target.is_none() {
+        let data = json.as_ref().and_then(|json| json.get("data"));
+        target = ["job_id", "id"]
+            .iter()
+            .find_map(|key| data?.get(key)?.as_str())
+            .map(str::to_string);
+    }
+
+    audit.record(AuditEntry {
+        timestamp: Utc::now(),
+        request_id: request_id.clone(),
+        principal: principal.id,
+        principal_name: principal.name,
+        ip,
+        action: action.to_string(),
+        method,
+        path,
+        target,
+        status: status.as_u16(),
+        outcome,
+        error,
+    })
+    .await;
+    with_request_id(Response::from_parts(parts, Body::from(bytes)), &request_id)
+}
+
+fn with_request_id(mut response: Response, request_id: &str) -> Response {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
@@ -1,14 +1,29 @@
 use axum::{
-    extract::{Query, Request, State},
+    extract::{ConnectInfo, Query, Request, State},
     http::{header, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::network::ClientIp;
+use crate::ratelimit::too_many_requests;
+use crate::signing::{self, SignatureError, SignatureVerifier};
+use crate::tokens::{Principal, Scope, TokenRegistry};
+use crate::unix::PeerCred;
 
 #[derive(Clone)]
 pub struct AuthState {
-    pub password: String,
+    pub tokens: Arc<TokenRegistry>,
+    pub failures: Arc<FailureTracker>,
+    pub signatures: Arc<SignatureVerifier>,
+    // 是否接受 ?token= 查询参数，关闭后只能使用 Authorization 请求头
+    pub allow_query_token: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -16,39 +31,328 @@ pub struct AuthQuery {
     token: Option<String>,
 }
 
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// 按 IP 统计认证失败次数，连续失败达到上限后锁定一段时间
+pub struct FailureTracker {
+    // 0 表示不限制
+    max_failures: u32,
+    lockout: Duration,
+    entries: Mutex<HashMap<IpAddr, Failures>>,
+}
+
+impl FailureTracker {
+    pub fn new(max_failures: u32, lockout: Duration) -> Self {
+        Self {
+            max_failures,
+            lockout,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// IP 处于锁定期时返回剩余时长
+    fn locked(&self, ip: IpAddr) -> Option<Duration> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let until = entries.get(&ip)?.locked_until?;
+        until.checked_duration_since(Instant::now())
+    }
+
+    /// 记录一次失败，失败间隔超过锁定时长时重新计数
+    fn record_failure(&self, ip: IpAddr) {
+        if self.max_failures == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, entry| {
+            now.duration_since(entry.last) < self.lockout
+                || entry.locked_until.is_some_and(|until| until > now)
+        });
+
+        let entry = entries.entry(ip).or_insert(Failures {
+            count: 0,
+            last: now,
+            locked_until: None,
+        });
+        entry.count += 1;
+        entry.last = now;
+        if entry.count >= self.max_failures {
+            warn!(
+                "{} failed authentication {} times, locked for {}s",
+                ip,
+                entry.count,
+                self.lockout.as_secs()
+            );
+            entry.count = 0;
+            entry.locked_until = Some(now + self.lockout);
+        }
+    }
+
+    fn record_success(&self, ip: IpAddr) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&ip);
+    }
+}
+
+/// 请求的来源 IP：优先使用 network_middleware 解析的地址（经可信代理时取 X-Forwarded-For），否则为连接地址
+pub fn client_ip(request: &Request) -> Option<IpAddr> {
+    if let Some(ClientIp(ip)) = request.extensions().get::<ClientIp>() {
+        return Some(*ip);
+    }
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// 认证成功后检查令牌的来源网段限制，通过后放入调用方
+async fn admit(
+    state: &AuthState,
+    ip: Option<IpAddr>,
+    principal: Principal,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(ip) = ip {
+        state.failures.record_success(ip);
+    }
+    if !principal.allows_ip(ip) {
+        return (
+            StatusCode::FORBIDDEN,
+            format!("Forbidden: token {} is not allowed from this address", principal.name),
+        )
+            .into_response();
+    }
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
 pub async fn auth_middleware(
     State(state): State<AuthState>,
     Query(query): Query<AuthQuery>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
-    // 如果没有设置密码，直接放行
-    if state.password.is_empty() {
+    // 如果没有配置任何令牌，直接放行
+    if state.tokens.is_empty().await {
+        request.extensions_mut().insert(Principal::anonymous());
         return next.run(request).await;
     }
 
-    // 检查 query 参数 ?token=xxx
-    if let Some(token) = query.token {
-        if token == state.password {
-            return next.run(request).await;
-        }
+    let ip = client_ip(&request);
+    if let Some(remaining) = ip.and_then(|ip| state.failures.locked(ip)) {
+        return too_many_requests(
+            "Too many failed authentication attempts, try again later",
+            remaining,
+        );
+    }
+
+    // 携带 X-Bark-Key-Id 的请求按 HMAC 签名认证，不再读取令牌
+    if signing::is_signed(&request) {
+        return match state.signatures.verify(&state.tokens, request).await {
+            Ok((request, principal)) => admit(&state, ip, principal, request, next).await,
+            Err(SignatureError::TooLarge) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response()
+            }
+            Err(SignatureError::Invalid(reason)) => {
+                if let Some(ip) = ip {
+                    state.failures.record_failure(ip);
+                }
+                (StatusCode::UNAUTHORIZED, format!("Unauthorized: {}", reason)).into_response()
+            }
+        };
+    }
+
+    // 检查 query 参数 ?token=xxx，其次是 Authorization header: Bearer xxx
+    if query.token.is_some() && !state.allow_query_token {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized: ?token= is disabled, use the Authorization header",
+        )
+            .into_response();
     }
+    let token = query.token.or_else(|| {
+        let auth_str = request.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+        // 支持 "Bearer xxx" 或 "xxx"
+        Some(auth_str.strip_prefix("Bearer ").unwrap_or(auth_str).trim().to_string())
+    });
 
-    // 检查 Authorization header: Bearer xxx
-    if let Some(auth_header) = request.headers().get(header::AUTHORIZATION) {
-        if let Ok(auth_str) = auth_header.to_str() {
-            // 支持 "Bearer xxx" 或 "xxx"
-            let token = auth_str
-                .strip_prefix("Bearer ")
-                .unwrap_or(auth_str)
-                .trim();
-            
-            if token == state.password {
-                return next.run(request).await;
+    // 未携带令牌不计入失败次数；经 Unix socket 连接时按对端 UID 认证
+    let Some(token) = token else {
+        if let Some(peer) = request.extensions().get::<PeerCred>().copied() {
+            return match state.tokens.authenticate_uid(peer.uid).await {
+                Ok(principal) => admit(&state, ip, principal, request, next).await,
+                Err(reason) => {
+                    (StatusCode::UNAUTHORIZED, format!("Unauthorized: {}", reason)).into_response()
+                }
+            };
+        }
+        return (StatusCode::UNAUTHORIZED, "Unauthorized: invalid or missing token").into_response();
+    };
+    match state.tokens.authenticate(&token).await {
+        Ok(principal) => admit(&state, ip, principal, request, next).await,
+        // 验证失败
+        Err(reason) => {
+            if let Some(ip) = ip {
+                state.failures.record_failure(ip);
             }
+            (StatusCode::UNAUTHORIZED, format!("Unauthorized: {}", reason)).into_response()
+        }
+    }
+}
+
+/// 按路由检查调用方的权限范围，需在 auth_middleware 之后执行
+pub async fn require_scope(State(scope): State<Scope>, request: Request, next: Next) -> Response {
+    let allowed = request
+        .extensions()
+        .get::<Principal>()
+        .is_some_and(|principal| principal.has(scope));
+    if !allowed {
+        return (
+            StatusCode::FORBIDDEN,
+            format!("Forbidden: requires scope {}", scope.as_str()),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    use crate::tokens::{AuthKind, TokenConfig};
+
+    const PEER: ([u8; 4], u16) = ([192, 0, 2, 1], 40000);
+
+    fn auth_state(max_failures: u32, allow_query_token: bool) -> AuthState {
+        let token = TokenConfig {
+            name: "agent".to_string(),
+            token: "tok-agent".to_string(),
+            auth: AuthKind::Bearer,
+            scopes: vec![Scope::Notify],
+            expires_at: None,
+            devices: Vec::new(),
+            rate_limit: None,
+            max_active_jobs: None,
+            allowed_networks: Vec::new(),
+            unix_uids: Vec::new(),
+        };
+        AuthState {
+            tokens: Arc::new(TokenRegistry::from_config("", &[token], "").unwrap()),
+            failures: Arc::new(FailureTracker::new(max_failures, Duration::from_secs(60))),
+            signatures: Arc::new(SignatureVerifier::new(Duration::from_secs(300))),
+            allow_query_token,
+        }
+    }
+
+    fn app(state: AuthState) -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware))
+    }
+
+    async fn call(app: &Router, uri: &str, token: Option<&str>) -> Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
         }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(PEER)));
+        app.clone().oneshot(request).await.unwrap()
     }
 
-    // 验证失败
-    (StatusCode::UNAUTHORIZED, "Unauthorized: invalid or missing token").into_response()
+    #[tokio::test]
+    async fn require_scope_checks_the_principal() {
+        let app = Router::new()
+            .route("/jobs", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(Scope::JobsRead, require_scope))
+            .layer(middleware::from_fn_with_state(auth_state(5, true), auth_middleware));
+        // agent 令牌只有 notify 权限
+        let response = call(&app, "/jobs", Some("tok-agent")).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(body, "Forbidden: requires scope jobs:read");
+
+        let app = Router::new()
+            .route("/notify", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(Scope::Notify, require_scope))
+            .layer(middleware::from_fn_with_state(auth_state(5, true), auth_middleware));
+        assert_eq!(call(&app, "/notify", Some("tok-agent")).await.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn locks_after_max_failures() {
+        let tracker = FailureTracker::new(3, Duration::from_secs(60));
+        let ip = IpAddr::from([192, 0, 2, 1]);
+        tracker.record_failure(ip);
+        tracker.record_failure(ip);
+        assert!(tracker.locked(ip).is_none());
+        tracker.record_failure(ip);
+        let remaining = tracker.locked(ip).unwrap();
+        assert!(remaining <= Duration::from_secs(60) && remaining > Duration::from_secs(55));
+        // 其他 IP 不受影响
+        assert!(tracker.locked(IpAddr::from([192, 0, 2, 2])).is_none());
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let tracker = FailureTracker::new(2, Duration::from_secs(60));
+        let ip = IpAddr::from([192, 0, 2, 1]);
+        tracker.record_failure(ip);
+        tracker.record_success(ip);
+        tracker.record_failure(ip);
+        assert!(tracker.locked(ip).is_none());
+    }
+
+    #[test]
+    fn zero_max_failures_never_locks() {
+        let tracker = FailureTracker::new(0, Duration::from_secs(60));
+        let ip = IpAddr::from([192, 0, 2, 1]);
+        for _ in 0..10 {
+            tracker.record_failure(ip);
+        }
+        assert!(tracker.locked(ip).is_none());
+    }
+
+    #[tokio::test]
+    async fn lockout_returns_429_with_retry_after() {
+        let app = app(auth_state(2, true));
+        assert_eq!(call(&app, "/", Some("wrong")).await.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(call(&app, "/", Some("wrong")).await.status(), StatusCode::UNAUTHORIZED);
+        // 锁定期内即使令牌正确也拒绝
+        let response = call(&app, "/", Some("tok-agent")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+    }
+
+    #[tokio::test]
+    async fn missing_token_is_not_a_failure() {
+        let app = app(auth_state(1, true));
+        assert_eq!(call(&app, "/", None).await.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(call(&app, "/", Some("tok-agent")).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn query_token_can_be_disabled() {
+        let enabled = app(auth_state(5, true));
+        assert_eq!(call(&enabled, "/?token=tok-agent", None).await.status(), StatusCode::OK);
+
+        let disabled = app(auth_state(5, false));
+        let response = call(&disabled, "/?token=tok-agent", None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("?token= is disabled"));
+        assert_eq!(call(&disabled, "/", Some("tok-agent")).await.status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+
+use crate::scheduler::{
+    validate_cron_request, validate_one_time_request, JobFilter, NotificationScheduler,
+    OneTimeRequest, ScheduleRequest,
+};
+use crate::notify::NotifyRequest;
+use crate::template::TemplateStore;
+use crate::tokens::{Principal, Scope};
+
+// 单次批量创建的任务数上限
+const MAX_BULK_CREATE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum BulkRequest {
+    // 批量创建，全部校验通过才会创建
+    Create { jobs: Vec<BulkJob> },
+    Delete(JobSelector),
+    Pause(JobSelector),
+    Resume(JobSelector),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BulkJob {
+    Cron(Box<ScheduleRequest>),
+    Once(Box<OneTimeRequest>),
+}
+
+impl BulkJob {
+    fn outbound_urls(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            BulkJob::Cron(req) => req.outbound_urls(),
+            BulkJob::Once(req) => req.outbound_urls(),
+        }
+    }
+
+    fn check_callback(&self, scheduler: &NotificationScheduler) -> anyhow::Result<()> {
+        let (url, secret) = match self {
+            BulkJob::Cron(req) => (&req.callback_url, &req.callback_secret),
+            BulkJob::Once(req) => (&req.callback_url, &req.callback_secret),
+        };
+        scheduler.check_callback(url.as_deref(), secret.as_deref())
+    }
+}
+
+/// 按 ID 列表或筛选条件选择任务
+#[derive(Debug, Deserialize)]
+pub struct JobSelector {
+    #[serde(default)]
+    pub ids: Vec<String>,
+    pub filter: Option<JobFilter>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkItemResult {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BulkItemResult {
+    fn ok(index: usize, job_id: String) -> Self {
+        Self {
+            index,
+            job_id: Some(job_id),
+            success: true,
+            error: None,
+        }
+    }
+
+    fn failed(index: usize, job_id: Option<String>, error: impl ToString) -> Self {
+        Self {
+            index,
+            job_id,
+            success: false,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// 批量操作结果；error 不为空表示整体失败（如批量创建校验未通过）
+pub struct BulkOutcome {
+    pub results: Vec<BulkItemResult>,
+    pub error: Option<String>,
+}
+
+pub async fn run(
+    scheduler: &NotificationScheduler,
+    templates: &TemplateStore,
+    principal: &Principal,
+    quota: Option<usize>,
+    req: BulkRequest,
+) -> anyhow::Result<BulkOutcome> {
+    match req {
+        BulkRequest::Create { jobs } => {
+            if !principal.has(Scope::Schedule) {
+                return Err(anyhow::anyhow!("Creating jobs requires scope schedule"));
+            }
+            create(scheduler, templates, principal, quota, jobs).await
+        }
+        BulkRequest::Delete(selector) => {
+            let ids = select(scheduler, principal, selector).await?;
+            let mut results = Vec::with_capacity(ids.len());
+            for (index, id) in ids.into_iter().enumerate() {
+                let result = match check_owner(scheduler, principal, &id).await {
+                    Ok(_) => scheduler.remove_job(&id).await,
+                    Err(e) => Err(e),
+                };
+                results.push(item_result(index, id, result));
+            }
+            Ok(BulkOutcome {
+                results,
+                error: None,
+            })
+        }
+        BulkRequest::Pause(selector) => {
+            let ids = select(scheduler, principal, selector).await?;
+            let mut results = Vec::with_capacity(ids.len());
+            for (index, id) in ids.into_iter().enumerate() {
+                let result = match check_owner(scheduler, principal, &id).await {
+                    Ok(_) => scheduler.pause_job(&id).await,
+                    Err(e) => Err(e),
+                };
+                results.push(item_result(index, id, result));
+            }
+            Ok(BulkOutcome {
+                results,
+                error: None,
+            })
+        }
+        BulkRequest::Resume(selector) => {
+            let ids = select(scheduler, principal, selector).await?;
+            let mut results = Vec::with_capacity(ids.len());
+            for (index, id) in ids.into_iter().enumerate() {
+                let result = match check_owner(scheduler, principal, &id).await {
+                    Ok(_) => scheduler.resume_job(&id).await,
+                    Err(e) => Err(e),
+                };
+                results.push(item_result(index, id, result));
+            }
+            Ok(BulkOutcome {
+                results,
+                error: None,
+            })
+        }
+    }
+}
+
+/// 其他令牌的任务按不存在处理
+async fn check_owner(
+    scheduler: &NotificationScheduler,
+    principal: &Principal,
+    job_id: &str,
+) -> anyhow::Result<()> {
+    match scheduler.get_job(job_id).await {
+        Some(job) if principal.owns(&job.owner) => Ok(()),
+        _ => Err(anyhow::anyhow!("Job {} not found", job_id)),
+    }
+}
+
+fn check_device(
+    scheduler: &NotificationScheduler,
+    principal: &Principal,
+    notify: &NotifyRequest,
+) -> anyhow::Result<()> {
+    let device = notify.device.as_deref();
+    principal.check_device(device)?;
+    scheduler.check_device(device)
+}
+
+fn item_result(index: usize, job_id: String, result: anyhow::Result<()>) -> BulkItemResult {
+    match result {
+        Ok(_) => BulkItemResult::ok(index, job_id),
+        Err(e) => BulkItemResult::failed(index, Some(job_id), e),
+    }
+}
+
+async fn create(
+    scheduler: &NotificationScheduler,
+    templates: &TemplateStore,
+    principal: &Principal,
+    quota: Option<usize>,
+    jobs: Vec<BulkJob>,
+) -> anyhow::Result<BulkOutcome> {
+    if jobs.is_empty() {
+        return Err(anyhow::anyhow!("No jobs to create"));
+    }
+    if jobs.len() > MAX_BULK_CREATE {
+        return Err(anyhow::anyhow!(
+            "At most {} jobs can be created at once",
+            MAX_BULK_CREATE
+        ));
+    }
+    scheduler.check_job_quota(&principal.id, quota, jobs.len()).await?;
+
+    // 先合并模板并校验全部任务，任一失败则一个都不创建
+    let mut resolved = Vec::with_capacity(jobs.len());
+    let mut results = Vec::new();
+    for (index, job) in jobs.into_iter().enumerate() {
+        let checked = match job {
+            BulkJob::Cron(mut req) => match templates.resolve(req.notify).await {
+                Ok(notify) => {
+                    req.notify = notify;
+                    validate_cron_request(&req)
+                        .and_then(|_| check_device(scheduler, principal, &req.notify))
+                        .map(|_| BulkJob::Cron(req))
+                }
+                Err(e) => Err(e),
+            },
+            BulkJob::Once(mut req) => match templates.resolve(req.notify).await {
+                Ok(notify) => {
+                    req.notify = notify;
+                    validate_one_time_request(&mut req)
+                        .and_then(|_| check_device(scheduler, principal, &req.notify))
+                        .map(|_| BulkJob::Once(req))
+                }
+                Err(e) => Err(e),
+            },
+        };
+        let checked = checked.and_then(|job| job.check_callback(scheduler).map(|_| job));
+        // 外部地址需要解析域名，放在同步校验之后
+        let checked = match checked {
+            Ok(job) => scheduler.check_outbound(&job.outbound_urls()).await.map(|_| job),
+            Err(e) => Err(e),
+        };
+        match checked {
+            Ok(job) => resolved.push(job),
+            Err(e) => results.push(BulkItemResult::failed(index, None, e)),
+        }
+    }
+    if !results.is_empty() {
+        return Ok(BulkOutcome {
+            results,
+            error: Some("Validation failed, no jobs were created".to_string()),
+        });
+    }
+
+    let mut created = Vec::with_capacity(resolved.len());
+    for (index, job) in resolved.into_iter().enumerate() {
+        let result = match job {
+            BulkJob::Cron(req) => scheduler.add_cron_job(*req, &principal.id, quota).await,
+            BulkJob::Once(req) => scheduler.add_one_time_job(*req, &principal.id, quota).await,
+        };
+        match result {
+            Ok(job_id) => created.push(BulkItemResult::ok(index, job_id)),
+            Err(e) => {
+                // 静默回滚已创建的任务，客户端从未看到它们创建成功
+                for item in &created {
+                    if let Some(job_id) = &item.job_id {
+                        scheduler.discard_job(job_id).await;
+                    }
+                }
+                return Ok(BulkOutcome {
+                    results: vec![BulkItemResult::failed(index, None, e)],
+                    error: Some("Failed to create job, created jobs were rolled back".to_string()),
+                });
+            }
+        }
+    }
+
+    Ok(BulkOutcome {
+        results: created,
+        error: None,
+    })
+}
+
+async fn select(
+    scheduler: &NotificationScheduler,
+    principal: &Principal,
+    selector: JobSelector,
+) -> anyhow::Result<Vec<String>> {
+    match (selector.ids.is_empty(), selector.filter) {
+        (false, None) => Ok(selector.ids),
+        (true, Some(mut filter)) if !filter.is_empty() => {
+            // 非 admin 只能选中自己的任务
+            if let Some(owner) = principal.owner_filter() {
+                filter.owner = Some(owner);
+            }
+            Ok(scheduler.find_job_ids(&filter).await)
+        }
+        (true, Some(_)) => Err(anyhow::anyhow!(
+            "Filter must contain at least one condition"
+        )),
+        (false, Some(_)) => Err(anyhow::anyhow!("Specify either ids or filter, not both")),
+        (true, None) => Err(anyhow::anyhow!("Specify ids or filter")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callback::CallbackDispatcher;
+    use crate::events::{EventBus, EventKind};
+    use crate::notify::Notifier;
+    use crate::outbound::OutboundPolicy;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    async fn scheduler(events: Arc<EventBus>) -> NotificationScheduler {
+        let outbound = Arc::new(OutboundPolicy::new(Vec::new()));
+        // 未配置默认回调密钥
+        let callbacks = Arc::new(CallbackDispatcher::new(None, Arc::clone(&outbound)).unwrap());
+        let devices = BTreeMap::from([("default".to_string(), "key".to_string())]);
+        let notifier = Arc::new(Notifier::new("http://93.184.216.34".to_string(), devices));
+        NotificationScheduler::new(notifier, events, callbacks, outbound)
+            .await
+            .unwrap()
+    }
+
+    fn create_request(jobs: serde_json::Value) -> BulkRequest {
+        serde_json::from_value(serde_json::json!({"action": "create", "jobs": jobs})).unwrap()
+    }
+
+    #[tokio::test]
+    async fn missing_callback_secret_fails_validation_before_creating() {
+        let scheduler = scheduler(Arc::new(EventBus::new())).await;
+        let req = create_request(serde_json::json!([
+            {"type": "cron", "title": "t", "body": "b", "every": "1h"},
+            {"type": "cron", "title": "t", "body": "b", "every": "1h",
+             "callback_url": "http://93.184.216.34/hook"},
+        ]));
+        let outcome = run(&scheduler, &TemplateStore::new(), &Principal::anonymous(), None, req)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            outcome.error.as_deref(),
+            Some("Validation failed, no jobs were created")
+        );
+        assert_eq!(outcome.results.len(), 1);
+        assert_eq!(outcome.results[0].index, 1);
+        assert!(outcome.results[0].job_id.is_none());
+        assert_eq!(scheduler.job_count(Duration::from_secs(1)).await, Some(0));
+    }
+
+    #[tokio::test]
+    async fn discarded_jobs_publish_no_cancel_event() {
+        let events = Arc::new(EventBus::new());
+        let scheduler = scheduler(Arc::clone(&events)).await;
+        let req: ScheduleRequest = serde_json::from_value(serde_json::json!({
+            "title": "t", "body": "b", "every": "1h",
+            "callback_url": "http://93.184.216.34/hook", "callback_secret": "s"
+        }))
+        .unwrap();
+        let mut receiver = events.receiver();
+        let job_id = scheduler.add_cron_job(req, "anonymous", None).await.unwrap();
+        scheduler.discard_job(&job_id).await;
+
+        assert!(scheduler.get_job(&job_id).await.is_none());
+        assert_eq!(receiver.try_recv().unwrap().kind, EventKind::JobCreated);
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,335 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Url};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::events::{Event, EventBus, EventKind};
+use crate::metrics::{CALLBACK_RETRIES, METRICS};
+use crate::outbound::{OutboundClient, OutboundPolicy};
+
+pub const SIGNATURE_HEADER: &str = "x-bark-signature";
+pub const TIMESTAMP_HEADER: &str = "x-bark-timestamp";
+pub const EVENT_HEADER: &str = "x-bark-event";
+pub const DELIVERY_HEADER: &str = "x-bark-delivery";
+
+// 每个事件最多投递次数，失败后按 1s、2s、4s... 退避重试
+const MAX_ATTEMPTS: u32 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// 每个任务保留的投递记录条数
+const LOG_LIMIT: usize = 100;
+// 已结束任务的投递记录保留时长
+const LOG_RETENTION: chrono::Duration = chrono::Duration::hours(24);
+
+/// 校验回调地址，只允许 http / https
+pub fn validate_url(url: &str) -> anyhow::Result<()> {
+    let parsed =
+        Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid callback_url '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(anyhow::anyhow!("callback_url must use http or https"));
+    }
+    Ok(())
+}
+
+/// 对 "{timestamp}.{body}" 计算 HMAC-SHA256，返回十六进制签名
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// 一次投递尝试的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryRecord {
+    pub delivery_id: String,
+    pub event_id: u64,
+    pub event: EventKind,
+    pub attempt: u32,
+    pub timestamp: DateTime<Utc>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+struct Registration {
+    url: String,
+    secret: String,
+}
+
+#[derive(Default)]
+struct DeliveryLog {
+    // 任务所属的令牌 ID
+    owner: String,
+    records: VecDeque<DeliveryRecord>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+/// 任务回调：订阅事件流，把执行结果、完成和取消事件签名后 POST 给任务的 callback_url
+pub struct CallbackDispatcher {
+    client: OutboundClient,
+    // 未在请求中指定 callback_secret 时使用的签名密钥
+    default_secret: Option<String>,
+    registrations: RwLock<HashMap<String, Arc<Registration>>>,
+    logs: RwLock<HashMap<String, DeliveryLog>>,
+}
+
+impl CallbackDispatcher {
+    pub fn new(default_secret: Option<String>, outbound: Arc<OutboundPolicy>) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: OutboundClient::new(outbound, Client::builder().timeout(REQUEST_TIMEOUT))?,
+            default_secret: default_secret.filter(|secret| !secret.is_empty()),
+            registrations: RwLock::new(HashMap::new()),
+            logs: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// 确认可以为回调签名：请求未带密钥时需要配置默认密钥
+    pub fn check_secret(&self, secret: Option<&str>) -> anyhow::Result<()> {
+        if secret.is_some_and(|s| !s.is_empty()) || self.default_secret.is_some() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "callback_url requires a callback_secret in the request or callback_secret in the server config"
+            ))
+        }
+    }
+
+    pub async fn register(&self, job_id: &str, owner: &str, url: String, secret: Option<String>) {
+        let Some(secret) = secret
+            .filter(|s| !s.is_empty())
+            .or_else(|| self.default_secret.clone())
+        else {
+            return;
+        };
+        self.registrations
+            .write()
+            .await
+            .insert(job_id.to_string(), Arc::new(Registration { url, secret }));
+        self.logs
+            .write()
+            .await
+            .insert(
+                job_id.to_string(),
+                DeliveryLog {
+                    owner: owner.to_string(),
+                    ..Default::default()
+                },
+            );
+    }
+
+    /// 撤销注册，用于任务创建失败时
+    pub async fn unregister(&self, job_id: &str) {
+        self.registrations.write().await.remove(job_id);
+        self.logs.write().await.remove(job_id);
+    }
+
+    /// 任务所属的令牌和投递记录，任务从未设置回调时返回 None
+    pub async fn log(&self, job_id: &str) -> Option<(String, Vec<DeliveryRecord>)> {
+        self.logs
+            .read()
+            .await
+            .get(job_id)
+            .map(|log| (log.owner.clone(), log.records.iter().cloned().collect()))
+    }
+
+    /// 在后台消费事件流
+    pub fn start(self: &Arc<Self>, events: &EventBus) {
+        let mut receiver = events.receiver();
+        let dispatcher = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => dispatcher.handle(event).await,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Callback dispatcher lagged, {} events skipped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn handle(self: &Arc<Self>, event: Event) {
+        // 只回调执行结果和任务结束，创建、触发事件不回调
+        if matches!(event.kind, EventKind::JobCreated | EventKind::JobFired) {
+            return;
+        }
+        let Some(job_id) = event.job_id.clone() else {
+            return;
+        };
+
+        let terminal = matches!(event.kind, EventKind::JobCompleted | EventKind::JobCancelled);
+        let registration = if terminal {
+            let registration = self.registrations.write().await.remove(&job_id);
+            if registration.is_some()
+                && let Some(log) = self.logs.write().await.get_mut(&job_id)
+            {
+                log.finished_at = Some(Utc::now());
+            }
+            registration
+        } else {
+            self.registrations.read().await.get(&job_id).cloned()
+        };
+        let Some(registration) = registration else {
+            return;
+        };
+
+        // 每个事件单独投递和重试，同一任务的回调不保证按事件顺序到达，接收方应以 event id 排序
+        let dispatcher = Arc::clone(self);
+        tokio::spawn(async move {
+            dispatcher.deliver(&job_id, &registration, &event).await;
+        });
+    }
+
+    async fn deliver(&self, job_id: &str, registration: &Registration, event: &Event) {
+        let body = serde_json::to_string(event).unwrap_or_default();
+        let delivery_id = Uuid::new_v4().to_string();
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let timestamp = Utc::now();
+            let signature = sign(&registration.secret, timestamp.timestamp(), &body);
+            let started = Instant::now();
+            let result = match self.client.post(&registration.url) {
+                Ok(request) => request
+                    .header("content-type", "application/json")
+                    .header(SIGNATURE_HEADER, format!("sha256={}", signature))
+                    .header(TIMESTAMP_HEADER, timestamp.timestamp().to_string())
+                    .header(EVENT_HEADER, event.kind.as_str())
+                    .header(DELIVERY_HEADER, &delivery_id)
+                    .body(body.clone())
+                    .send()
+                    .await
+                    .map_err(anyhow::Error::from),
+                Err(e) => Err(e),
+            };
+
+            let (success, status, error, retry) = match result {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let retry = status.is_server_error() || status.as_u16() == 429;
+                    let error = (!status.is_success()).then(|| format!("HTTP {}", status));
+                    (status.is_success(), Some(status.as_u16()), error, retry)
+                }
+                Err(e) => (false, None, Some(e.to_string()), true),
+            };
+            self.record(
+                job_id,
+                DeliveryRecord {
+                    delivery_id: delivery_id.clone(),
+                    event_id: event.id,
+                    event: event.kind,
+                    attempt,
+                    timestamp,
+                    success,
+                    status,
+                    error: error.clone(),
+                    duration_ms: started.elapsed().as_millis() as u64,
+                },
+            )
+            .await;
+
+            if success {
+                info!("Callback for job {} delivered ({:?})", job_id, event.kind);
+                return;
+            }
+            warn!(
+                "Callback for job {} failed (attempt {}/{}): {}",
+                job_id,
+                attempt,
+                MAX_ATTEMPTS,
+                error.unwrap_or_default()
+            );
+            if !retry {
+                return;
+            }
+            if attempt < MAX_ATTEMPTS {
+                METRICS.inc(CALLBACK_RETRIES, &[]);
+                tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+            }
+        }
+    }
+
+    async fn record(&self, job_id: &str, record: DeliveryRecord) {
+        let mut logs = self.logs.write().await;
+        // 清理过期的已结束任务记录
+        let cutoff = Utc::now() - LOG_RETENTION;
+        logs.retain(|_, log| log.finished_at.is_none_or(|at| at > cutoff));
+
+        // 记录已被清理或撤销（任务结束超过保留时长、创建失败回滚）时丢弃，
+        // 不能重建一条没有 owner 的记录
+        let Some(log) = logs.get_mut(job_id) else {
+            return;
+        };
+        if log.records.len() == LOG_LIMIT {
+            log.records.pop_front();
+        }
+        log.records.push_back(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dispatcher() -> CallbackDispatcher {
+        CallbackDispatcher::new(None, Arc::new(OutboundPolicy::new(Vec::new()))).unwrap()
+    }
+
+    fn record(event_id: u64) -> DeliveryRecord {
+        DeliveryRecord {
+            delivery_id: "d".to_string(),
+            event_id,
+            event: EventKind::DeliverySucceeded,
+            attempt: 1,
+            timestamp: Utc::now(),
+            success: true,
+            status: Some(200),
+            error: None,
+            duration_ms: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn records_append_to_registered_jobs() {
+        let dispatcher = dispatcher();
+        dispatcher
+            .register("job", "owner", "http://93.184.216.34".to_string(), Some("s".to_string()))
+            .await;
+        dispatcher.record("job", record(1)).await;
+
+        let (owner, records) = dispatcher.log("job").await.unwrap();
+        assert_eq!(owner, "owner");
+        assert_eq!(records.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn records_do_not_resurrect_removed_logs() {
+        let dispatcher = dispatcher();
+        dispatcher
+            .register("job", "owner", "http://93.184.216.34".to_string(), Some("s".to_string()))
+            .await;
+        dispatcher.unregister("job").await;
+        dispatcher.record("job", record(1)).await;
+        assert!(dispatcher.log("job").await.is_none());
+
+        // 已结束且超过保留时长的记录被清理后，迟到的投递结果同样丢弃
+        dispatcher
+            .register("old", "owner", "http://93.184.216.34".to_string(), Some("s".to_string()))
+            .await;
+        if let Some(log) = dispatcher.logs.write().await.get_mut("old") {
+            log.finished_at = Some(Utc::now() - LOG_RETENTION - chrono::Duration::minutes(1));
+        }
+        dispatcher.record("old", record(2)).await;
+        assert!(dispatcher.log("old").await.is_none());
+    }
+}
@@ -0,0 +1,326 @@
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::content::{lookup, value_to_string};
+use crate::notify::NotifyRequest;
+use crate::outbound::{read_body, OutboundClient, MAX_TIMEOUT_SECS};
+
+/// 定时任务的触发条件：每次执行前探测目标地址，满足条件才发送通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Condition {
+    // 探测地址（GET）
+    pub url: String,
+    #[serde(default, skip_serializing)]
+    pub headers: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    // 期望的状态码，实际状态码不同或请求失败时触发
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect_status: Option<u16>,
+    // 响应 JSON 中要比较的字段路径，如 "$.data.cpu"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub op: Option<CompareOp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    // 为 true 时只在状态变化时通知：进入触发状态发送告警，恢复时发送恢复通知
+    #[serde(default)]
+    pub on_change: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_body: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareOp {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Ok,
+    Triggered,
+    // 响应无法判断（读取失败、不是 JSON、缺少字段），既不触发也不改变状态
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub outcome: ProbeOutcome,
+    pub detail: String,
+}
+
+impl ProbeResult {
+    fn new(outcome: ProbeOutcome, detail: String) -> Self {
+        Self { outcome, detail }
+    }
+}
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+// 状态变化模式下记录的上一次结果
+pub const STATE_UNKNOWN: u8 = 0;
+const STATE_OK: u8 = 1;
+const STATE_TRIGGERED: u8 = 2;
+
+impl Condition {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let url = reqwest::Url::parse(&self.url)
+            .map_err(|e| anyhow::anyhow!("Invalid condition url: {}", e))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(anyhow::anyhow!("condition url must be http or https"));
+        }
+
+        let json_check = [
+            self.json_path.is_some(),
+            self.op.is_some(),
+            self.value.is_some(),
+        ];
+        if json_check.iter().any(|set| *set) && !json_check.iter().all(|set| *set) {
+            return Err(anyhow::anyhow!(
+                "condition json_path, op and value must be set together"
+            ));
+        }
+        if self.expect_status.is_none() && self.json_path.is_none() {
+            return Err(anyhow::anyhow!(
+                "condition requires expect_status or json_path/op/value"
+            ));
+        }
+        if self
+            .timeout_secs
+            .is_some_and(|secs| secs == 0 || secs > MAX_TIMEOUT_SECS)
+        {
+            return Err(anyhow::anyhow!(
+                "condition timeout_secs must be between 1 and {}",
+                MAX_TIMEOUT_SECS
+            ));
+        }
+        Ok(())
+    }
+
+    async fn send(&self, client: &OutboundClient) -> anyhow::Result<Response> {
+        let timeout = Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let mut request = client.get(&self.url)?.timeout(timeout);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        Ok(request.send().await?)
+    }
+
+    /// 执行一次探测
+    pub async fn evaluate(&self, client: &OutboundClient) -> ProbeResult {
+        let response = match self.send(client).await {
+            Ok(response) => response,
+            Err(e) => {
+                return ProbeResult::new(ProbeOutcome::Triggered, format!("请求失败: {}", e));
+            }
+        };
+
+        let status = response.status().as_u16();
+        if let Some(expected) = self.expect_status
+            && status != expected
+        {
+            return ProbeResult::new(
+                ProbeOutcome::Triggered,
+                format!("状态码 {}，期望 {}", status, expected),
+            );
+        }
+
+        if let (Some(path), Some(op), Some(expected)) = (&self.json_path, self.op, &self.value) {
+            let body = match read_body(response).await {
+                Ok(body) => body,
+                Err(e) => {
+                    return ProbeResult::new(ProbeOutcome::Error, format!("读取响应失败: {}", e));
+                }
+            };
+            let data: Value = match serde_json::from_slice(&body) {
+                Ok(data) => data,
+                Err(e) => {
+                    return ProbeResult::new(
+                        ProbeOutcome::Error,
+                        format!("响应不是有效的 JSON: {}", e),
+                    );
+                }
+            };
+            return match lookup(&data, path) {
+                Some(actual) => {
+                    let outcome = if compare(actual, op, expected) {
+                        ProbeOutcome::Triggered
+                    } else {
+                        ProbeOutcome::Ok
+                    };
+                    ProbeResult::new(outcome, format!("{} = {}", path, value_to_string(actual)))
+                }
+                None => ProbeResult::new(ProbeOutcome::Error, format!("响应中没有字段 {}", path)),
+            };
+        }
+
+        ProbeResult::new(ProbeOutcome::Ok, format!("状态码 {}", status))
+    }
+
+    /// 根据探测结果决定本次要发送的通知，None 表示本次不通知
+    pub fn decide(
+        &self,
+        result: &ProbeResult,
+        last_state: &AtomicU8,
+        base: &NotifyRequest,
+    ) -> Option<NotifyRequest> {
+        let state = match result.outcome {
+            ProbeOutcome::Triggered => STATE_TRIGGERED,
+            ProbeOutcome::Ok => STATE_OK,
+            ProbeOutcome::Error => {
+                // 无法判断时保留上一次状态，避免误报故障或恢复
+                warn!("Condition probe {} failed: {}", self.url, result.detail);
+                return None;
+            }
+        };
+        let previous = last_state.swap(state, Ordering::SeqCst);
+        info!(
+            "Condition probe {}: triggered={}, {}",
+            self.url,
+            state == STATE_TRIGGERED,
+            result.detail
+        );
+
+        if !self.on_change {
+            return (state == STATE_TRIGGERED).then(|| base.clone());
+        }
+
+        match (previous, state) {
+            (STATE_UNKNOWN | STATE_OK, STATE_TRIGGERED) => Some(base.clone()),
+            (STATE_TRIGGERED, STATE_OK) => {
+                let mut recovery = base.clone();
+                recovery.title = self
+                    .recovery_title
+                    .clone()
+                    .unwrap_or_else(|| format!("已恢复: {}", base.title));
+                recovery.body = self
+                    .recovery_body
+                    .clone()
+                    .unwrap_or_else(|| result.detail.clone());
+                Some(recovery)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn compare(actual: &Value, op: CompareOp, expected: &Value) -> bool {
+    if let (Some(a), Some(b)) = (as_number(actual), as_number(expected)) {
+        return match op {
+            CompareOp::Gt => a > b,
+            CompareOp::Gte => a >= b,
+            CompareOp::Lt => a < b,
+            CompareOp::Lte => a <= b,
+            CompareOp::Eq => a == b,
+            CompareOp::Ne => a != b,
+        };
+    }
+
+    // 非数字只支持相等比较
+    let equal = value_to_string(actual) == value_to_string(expected);
+    match op {
+        CompareOp::Eq => equal,
+        CompareOp::Ne => !equal,
+        _ => false,
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbound::OutboundPolicy;
+    use axum::{routing::get, Router};
+    use reqwest::Client;
+    use std::sync::Arc;
+
+    fn condition(value: Value) -> Condition {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn json_condition(url: &str, on_change: bool) -> Condition {
+        condition(serde_json::json!({
+            "url": url, "json_path": "$.cpu", "op": "gt", "value": 90, "on_change": on_change
+        }))
+    }
+
+    fn notify() -> NotifyRequest {
+        serde_json::from_value(serde_json::json!({"title": "告警", "body": "b"})).unwrap()
+    }
+
+    fn result(outcome: ProbeOutcome) -> ProbeResult {
+        ProbeResult::new(outcome, "detail".to_string())
+    }
+
+    #[test]
+    fn timeout_secs_is_bounded() {
+        let with_timeout = |secs: u64| {
+            condition(serde_json::json!({
+                "url": "https://example.com", "expect_status": 200, "timeout_secs": secs
+            }))
+            .validate()
+        };
+        assert!(with_timeout(1).is_ok());
+        assert!(with_timeout(MAX_TIMEOUT_SECS).is_ok());
+        assert!(with_timeout(0).is_err());
+        assert!(with_timeout(MAX_TIMEOUT_SECS + 1).is_err());
+    }
+
+    #[test]
+    fn probe_errors_neither_fire_nor_reset_state() {
+        let cond = json_condition("https://example.com", true);
+        let state = AtomicU8::new(STATE_UNKNOWN);
+
+        assert!(cond.decide(&result(ProbeOutcome::Triggered), &state, &notify()).is_some());
+        assert!(cond.decide(&result(ProbeOutcome::Error), &state, &notify()).is_none());
+        assert_eq!(state.load(Ordering::SeqCst), STATE_TRIGGERED);
+
+        // 错误之后恢复正常仍会发送恢复通知
+        let recovery = cond.decide(&result(ProbeOutcome::Ok), &state, &notify()).unwrap();
+        assert_eq!(recovery.title, "已恢复: 告警");
+
+        let always = json_condition("https://example.com", false);
+        assert!(always.decide(&result(ProbeOutcome::Error), &state, &notify()).is_none());
+        assert_eq!(state.load(Ordering::SeqCst), STATE_OK);
+    }
+
+    #[tokio::test]
+    async fn unreadable_responses_are_errors() {
+        let app = Router::new()
+            .route("/cpu", get(|| async { r#"{"cpu": 95}"# }))
+            .route("/text", get(|| async { "not json" }))
+            .route("/other", get(|| async { r#"{"mem": 1}"# }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let policy = OutboundPolicy::new(vec!["127.0.0.1/32".parse().unwrap()]);
+        let client = OutboundClient::new(Arc::new(policy), Client::builder()).unwrap();
+        let probe = |path: &str| json_condition(&format!("http://{}{}", addr, path), false);
+
+        assert_eq!(probe("/cpu").evaluate(&client).await.outcome, ProbeOutcome::Triggered);
+        assert_eq!(probe("/text").evaluate(&client).await.outcome, ProbeOutcome::Error);
+        assert_eq!(probe("/other").evaluate(&client).await.outcome, ProbeOutcome::Error);
+    }
+}
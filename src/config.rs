@@ -1,5 +1,20 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::network::Network;
+use crate::notify::DEFAULT_DEVICE;
+use crate::ratelimit::RateLimitConfig;
+use crate::tls::TlsConfig;
+use crate::tokens::TokenConfig;
+
+/// 服务监听的地址
+#[derive(Debug, Clone)]
+pub enum Listen {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppConfig {
@@ -8,16 +23,85 @@ pub struct AppConfig {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    // 设置后以 HTTPS 提供服务
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    // 监听地址：unix:/path/to.sock 或 host:port，为空时使用 host 和 port
+    #[serde(default)]
+    pub listen: String,
+    // 监听 Unix socket 时是否同时监听 host:port
+    #[serde(default)]
+    pub keep_tcp: bool,
+    // Unix socket 文件的权限和属主（"用户[:组]"，名称或数字 ID）
+    #[serde(default = "default_socket_mode")]
+    pub socket_mode: u32,
+    #[serde(default)]
+    pub socket_owner: String,
     
     // Bark settings
     #[serde(default = "default_bark_url")]
     pub bark_url: String,
     #[serde(default)]
     pub device_key: String,
+    // 其他设备：名称 -> 设备密钥，请求中用 device 字段指定
+    #[serde(default)]
+    pub devices: HashMap<String, String>,
     
+    // 允许访问的网段（CIDR 或 IP，为空表示不限制），以及可信任 X-Forwarded-For 的反向代理
+    #[serde(default)]
+    pub allowed_networks: Vec<Network>,
+    #[serde(default)]
+    pub trusted_proxies: Vec<Network>,
+    // 任务访问外部地址时默认拒绝内网地址，这些网段除外
+    #[serde(default)]
+    pub outbound_allowed_networks: Vec<Network>,
+
     // Auth settings
     #[serde(default)]
     pub password: String,
+    // 带权限范围的访问令牌
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+    // 保存通过 /admin/tokens 创建、轮换的令牌，重启后加载；为空表示只保存在内存中。
+    // bearer 令牌只存哈希，hmac 令牌的密钥以明文保存，文件权限为 0600
+    #[serde(default = "default_token_store")]
+    pub token_store: String,
+    // 同一 IP 连续认证失败的次数上限（0 表示不限制），达到后锁定 auth_lockout_secs 秒
+    #[serde(default = "default_auth_max_failures")]
+    pub auth_max_failures: u32,
+    #[serde(default = "default_auth_lockout_secs")]
+    pub auth_lockout_secs: u64,
+    // 是否允许 ?token= 查询参数认证（令牌会出现在日志和代理记录中）
+    #[serde(default = "default_allow_query_token")]
+    pub allow_query_token: bool,
+    // HMAC 签名请求的时间戳允许偏差（秒），窗口内同一 nonce 只能使用一次
+    #[serde(default = "default_signature_window_secs")]
+    pub signature_window_secs: u64,
+    // 审计日志文件（JSON Lines，为空表示不记录），超过 audit_max_bytes 后轮换，保留 audit_max_files 个旧文件
+    #[serde(default)]
+    pub audit_log: String,
+    #[serde(default = "default_audit_max_bytes")]
+    pub audit_max_bytes: u64,
+    #[serde(default = "default_audit_max_files")]
+    pub audit_max_files: usize,
+
+    // 按来源 IP、令牌、路由和设备限流，以及每个令牌的任务数上限
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+
+    // 幂等键保留时长（秒）
+    #[serde(default = "default_idempotency_ttl_secs")]
+    pub idempotency_ttl_secs: u64,
+
+    // 任务回调的默认签名密钥，请求中未指定 callback_secret 时使用
+    #[serde(default)]
+    pub callback_secret: String,
+
+    // /ready 是否主动探测 Bark 服务（{bark_url}/ping），以及探测结果缓存时长（秒）
+    #[serde(default)]
+    pub ready_probe: bool,
+    #[serde(default = "default_ready_probe_ttl_secs")]
+    pub ready_probe_ttl_secs: u64,
 }
 
 impl Default for AppConfig {
@@ -25,9 +109,32 @@ impl Default for AppConfig {
         Self {
             host: default_host(),
             port: default_port(),
+            tls: None,
+            listen: String::new(),
+            keep_tcp: false,
+            socket_mode: default_socket_mode(),
+            socket_owner: String::new(),
             bark_url: default_bark_url(),
             device_key: String::new(),
+            devices: HashMap::new(),
+            allowed_networks: Vec::new(),
+            trusted_proxies: Vec::new(),
+            outbound_allowed_networks: Vec::new(),
             password: String::new(),
+            tokens: Vec::new(),
+            token_store: default_token_store(),
+            auth_max_failures: default_auth_max_failures(),
+            auth_lockout_secs: default_auth_lockout_secs(),
+            allow_query_token: default_allow_query_token(),
+            signature_window_secs: default_signature_window_secs(),
+            audit_log: String::new(),
+            audit_max_bytes: default_audit_max_bytes(),
+            audit_max_files: default_audit_max_files(),
+            rate_limit: RateLimitConfig::default(),
+            idempotency_ttl_secs: default_idempotency_ttl_secs(),
+            callback_secret: String::new(),
+            ready_probe: false,
+            ready_probe_ttl_secs: default_ready_probe_ttl_secs(),
         }
     }
 }
@@ -35,14 +142,37 @@ impl Default for AppConfig {
 impl AppConfig {
     /// 验证配置是否有效
     pub fn validate(&self) -> anyhow::Result<()> {
-        if self.device_key.is_empty() {
+        if self.device_key.is_empty() && self.devices.is_empty() {
             return Err(anyhow::anyhow!(
                 "设备密钥 device_key 不能为空。\
                 请在 config.toml 中设置，或通过环境变量 BARK_DEVICE_KEY 传入。"
             ));
         }
+        if !self.device_key.is_empty() && self.devices.contains_key(DEFAULT_DEVICE) {
+            return Err(anyhow::anyhow!(
+                "device_key 已作为 default 设备，devices 中不能再定义 default"
+            ));
+        }
+        if let Some((name, _)) = self.devices.iter().find(|(_, key)| key.is_empty()) {
+            return Err(anyhow::anyhow!("设备 {} 的密钥不能为空", name));
+        }
+        self.rate_limit.validate()?;
+        self.listen()?;
         Ok(())
     }
+
+    /// 全部设备：device_key 作为 default 设备，加上 devices 中的设备
+    pub fn device_keys(&self) -> BTreeMap<String, String> {
+        let mut devices: BTreeMap<String, String> = self
+            .devices
+            .iter()
+            .map(|(name, key)| (name.clone(), key.clone()))
+            .collect();
+        if !self.device_key.is_empty() {
+            devices.insert(DEFAULT_DEVICE.to_string(), self.device_key.clone());
+        }
+        devices
+    }
 }
 
 fn default_bark_url() -> String {
@@ -51,6 +181,46 @@ fn default_bark_url() -> String {
 
 
 
+fn default_idempotency_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_ready_probe_ttl_secs() -> u64 {
+    30
+}
+
+fn default_token_store() -> String {
+    "tokens.json".to_string()
+}
+
+fn default_auth_max_failures() -> u32 {
+    10
+}
+
+fn default_auth_lockout_secs() -> u64 {
+    5 * 60
+}
+
+fn default_allow_query_token() -> bool {
+    true
+}
+
+fn default_signature_window_secs() -> u64 {
+    5 * 60
+}
+
+fn default_audit_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_audit_max_files() -> usize {
+    5
+}
+
+fn default_socket_mode() -> u32 {
+    0o660
+}
+
 fn default_host() -> String {
     "0.0.0.0".to_string()
 }
@@ -67,7 +237,24 @@ impl AppConfig {
             .add_source(config::Environment::with_prefix("BARK"))
             .build()?;
         
-        Ok(settings.try_deserialize().unwrap_or_default())
+        Ok(settings.try_deserialize()?)
+    }
+
+    /// 解析 listen 配置
+    pub fn listen(&self) -> anyhow::Result<Listen> {
+        if self.listen.is_empty() {
+            return Ok(Listen::Tcp(self.socket_addr()));
+        }
+        if let Some(path) = self.listen.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err(anyhow::anyhow!("listen 中的 Unix socket 路径不能为空"));
+            }
+            return Ok(Listen::Unix(PathBuf::from(path)));
+        }
+        self.listen
+            .parse()
+            .map(Listen::Tcp)
+            .map_err(|_| anyhow::anyhow!("无效的 listen 地址 {}，应为 unix:/path/to.sock 或 host:port", self.listen))
     }
 
     pub fn socket_addr(&self) -> SocketAddr {
@@ -76,3 +263,42 @@ impl AppConfig {
             .unwrap_or_else(|_| "0.0.0.0:3000".parse().unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(device_key: &str, devices: &[(&str, &str)]) -> AppConfig {
+        AppConfig {
+            device_key: device_key.to_string(),
+            devices: devices
+                .iter()
+                .map(|(name, key)| (name.to_string(), key.to_string()))
+                .collect(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn device_key_becomes_the_default_device() {
+        let config = config("key-default", &[("ipad", "key-ipad")]);
+        assert!(config.validate().is_ok());
+        let keys = config.device_keys();
+        assert_eq!(keys.get(DEFAULT_DEVICE).map(String::as_str), Some("key-default"));
+        assert_eq!(keys.get("ipad").map(String::as_str), Some("key-ipad"));
+    }
+
+    #[test]
+    fn devices_alone_are_enough() {
+        let config = config("", &[("ipad", "key-ipad")]);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.device_keys().len(), 1);
+    }
+
+    #[test]
+    fn rejects_invalid_device_configs() {
+        assert!(config("", &[]).validate().is_err());
+        assert!(config("key", &[(DEFAULT_DEVICE, "other")]).validate().is_err());
+        assert!(config("key", &[("ipad", "")]).validate().is_err());
+    }
+}
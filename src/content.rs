@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::notify::NotifyRequest;
+use crate::outbound::{read_body, OutboundClient, MAX_TIMEOUT_SECS};
+
+/// 任务触发时动态获取通知内容的 HTTP 数据源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentSource {
+    // GET 请求地址，响应应为 JSON（非 JSON 时整体作为字符串使用）
+    pub url: String,
+    // 附加请求头，可能包含密钥，不返回给客户端
+    #[serde(default, skip_serializing)]
+    pub headers: HashMap<String, String>,
+    // 请求超时秒数，默认 10 秒
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    // 为 true 时，响应 JSON 中与通知同名的字段（title、body、sound 等）直接覆盖通知
+    #[serde(default)]
+    pub merge: bool,
+    // 获取失败时使用的正文
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+}
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+impl ContentSource {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let url = reqwest::Url::parse(&self.url)
+            .map_err(|e| anyhow::anyhow!("Invalid content_source url: {}", e))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(anyhow::anyhow!("content_source url must be http or https"));
+        }
+        if self
+            .timeout_secs
+            .is_some_and(|secs| secs == 0 || secs > MAX_TIMEOUT_SECS)
+        {
+            return Err(anyhow::anyhow!(
+                "content_source timeout_secs must be between 1 and {}",
+                MAX_TIMEOUT_SECS
+            ));
+        }
+        Ok(())
+    }
+
+    /// 获取数据并生成本次要发送的通知；失败时使用 fallback 正文
+    pub async fn resolve(&self, client: &OutboundClient, base: &NotifyRequest) -> NotifyRequest {
+        match self.fetch(client).await {
+            Ok(data) => {
+                let mut req = base.clone();
+                req.title = render(&base.title, Some(&data));
+                req.body = render(&base.body, Some(&data));
+                if self.merge {
+                    merge_fields(&mut req, &data);
+                }
+                req
+            }
+            Err(e) => {
+                warn!("Failed to fetch content from {}: {}", self.url, e);
+                let mut req = base.clone();
+                req.title = render(&base.title, None);
+                req.body = self
+                    .fallback
+                    .clone()
+                    .unwrap_or_else(|| format!("内容获取失败: {}", e));
+                req
+            }
+        }
+    }
+
+    async fn fetch(&self, client: &OutboundClient) -> anyhow::Result<Value> {
+        let timeout = Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let mut request = client.get(&self.url)?.timeout(timeout);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("content source returned status {}", status));
+        }
+
+        let text = String::from_utf8_lossy(&read_body(response).await?).into_owned();
+        info!("Fetched content from {} ({} bytes)", self.url, text.len());
+        Ok(serde_json::from_str(&text).unwrap_or(Value::String(text)))
+    }
+}
+
+/// 按路径取 JSON 中的值，支持 `$.a.b[0]`、`a.b.0` 等写法，`$` 表示整体
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = value;
+
+    for segment in path.replace('[', ".").replace(']', "").split('.') {
+        if segment.is_empty() {
+            continue;
+        }
+        current = match current {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// 替换文本中的 `{{path}}` 占位符；取不到值时替换为空字符串
+pub fn render(template: &str, data: Option<&Value>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let path = &rest[start + 2..start + end];
+        if let Some(value) = data.and_then(|data| lookup(data, path)) {
+            output.push_str(&value_to_string(value));
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn merge_fields(req: &mut NotifyRequest, data: &Value) {
+    let Value::Object(map) = data else {
+        return;
+    };
+    let text = |key: &str| map.get(key).map(value_to_string);
+
+    if let Some(title) = text("title") {
+        req.title = title;
+    }
+    if let Some(body) = text("body") {
+        req.body = body;
+    }
+    for (key, field) in [
+        ("sound", &mut req.sound),
+        ("group", &mut req.group),
+        ("level", &mut req.level),
+        ("icon", &mut req.icon),
+        ("url", &mut req.url),
+        ("copy", &mut req.copy),
+    ] {
+        if let Some(value) = text(key) {
+            *field = Some(value);
+        }
+    }
+    if let Some(auto_copy) = map.get("auto_copy").and_then(Value::as_bool) {
+        req.auto_copy = Some(auto_copy);
+    }
+    if let Some(badge) = map.get("badge").and_then(Value::as_i64) {
+        req.badge = Some(badge as i32);
+    }
+}
@@ -0,0 +1,565 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::scheduler::RecurringSchedule;
+
+/// 触发规则的可读描述
+#[derive(Debug, Clone, Serialize)]
+pub struct Description {
+    pub en: String,
+    pub zh: String,
+}
+
+const WEEKDAYS: [(&str, &str, &str); 7] = [
+    ("SUN", "Sunday", "周日"),
+    ("MON", "Monday", "周一"),
+    ("TUE", "Tuesday", "周二"),
+    ("WED", "Wednesday", "周三"),
+    ("THU", "Thursday", "周四"),
+    ("FRI", "Friday", "周五"),
+    ("SAT", "Saturday", "周六"),
+];
+
+const MONTHS: [(&str, &str); 12] = [
+    ("JAN", "January"),
+    ("FEB", "February"),
+    ("MAR", "March"),
+    ("APR", "April"),
+    ("MAY", "May"),
+    ("JUN", "June"),
+    ("JUL", "July"),
+    ("AUG", "August"),
+    ("SEP", "September"),
+    ("OCT", "October"),
+    ("NOV", "November"),
+    ("DEC", "December"),
+];
+
+pub fn describe(schedule: &RecurringSchedule) -> Description {
+    match schedule {
+        RecurringSchedule::Cron(expr, _) => describe_cron(expr),
+        RecurringSchedule::RRule(text, _) => describe_rrule(text),
+        RecurringSchedule::Interval {
+            interval, anchor, ..
+        } => describe_interval(*interval, *anchor),
+    }
+}
+
+fn describe_interval(interval: Duration, anchor: DateTime<Utc>) -> Description {
+    let (en, zh) = duration_words(interval);
+    let anchor = anchor.format("%Y-%m-%d %H:%M:%S UTC");
+    Description {
+        en: format!("Every {}, aligned to {}", en, anchor),
+        zh: format!("每 {}，以 {} 为起点", zh, anchor),
+    }
+}
+
+fn duration_words(duration: Duration) -> (String, String) {
+    let mut secs = duration.as_secs();
+    let mut en = Vec::new();
+    let mut zh = Vec::new();
+    for (size, unit_en, unit_zh) in [
+        (86400, "day", "天"),
+        (3600, "hour", "小时"),
+        (60, "minute", "分钟"),
+        (1, "second", "秒"),
+    ] {
+        let value = secs / size;
+        secs %= size;
+        if value > 0 {
+            en.push(format!("{} {}{}", value, unit_en, if value > 1 { "s" } else { "" }));
+            zh.push(format!("{} {}", value, unit_zh));
+        }
+    }
+    (en.join(" "), zh.join(" "))
+}
+
+// ---------- cron ----------
+
+enum TimePart {
+    // 固定时刻，如 09:00
+    At(String, String),
+    // 重复频率，如 每 5 分钟
+    Repeat(String, String),
+}
+
+fn describe_cron(expr: &str) -> Description {
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let [sec, min, hour, dom, month, dow] = fields[..] else {
+        return Description {
+            en: format!("Cron expression {}", expr),
+            zh: format!("定时表达式 {}", expr),
+        };
+    };
+
+    let time = cron_time(sec, min, hour);
+    let (day_en, day_zh) = cron_days(dom, dow);
+    let (month_en, month_zh) = cron_months(month);
+    // 指定月份时，"每月 1 日" 写作 "每年 1、6 月 1 日"
+    let day_zh = match (month_zh.is_empty(), day_zh.strip_prefix("每月 ")) {
+        (false, Some(rest)) => format!(" {}", rest),
+        (false, None) => format!("的{}", day_zh),
+        (true, _) => day_zh,
+    };
+
+    let (en, zh) = match time {
+        TimePart::At(time_en, time_zh) => (
+            format!("At {} {}{}", time_en, day_en, month_en),
+            format!("{}{} {}", month_zh, day_zh, time_zh),
+        ),
+        TimePart::Repeat(time_en, time_zh) if day_en == "every day" && month_en.is_empty() => {
+            (capitalize(&time_en), time_zh)
+        }
+        TimePart::Repeat(time_en, time_zh) => (
+            format!("{}, {}{}", capitalize(&time_en), day_en, month_en),
+            format!("{}{}，{}", month_zh, day_zh, time_zh),
+        ),
+    };
+    Description {
+        en: format!("{} (UTC)", en),
+        zh: format!("{}（UTC）", zh),
+    }
+}
+
+fn cron_time(sec: &str, min: &str, hour: &str) -> TimePart {
+    let single = |field: &str| field.parse::<u32>().ok();
+    let list = |field: &str| -> Option<Vec<u32>> {
+        field.split(',').map(|v| v.parse::<u32>().ok()).collect()
+    };
+    let step = |field: &str| -> Option<u32> {
+        field
+            .strip_prefix("*/")
+            .or_else(|| field.strip_prefix("0/"))
+            .and_then(|n| n.parse().ok())
+    };
+
+    match (single(sec), single(min), list(hour)) {
+        (Some(s), Some(m), Some(hours)) => {
+            let times: Vec<String> = hours
+                .iter()
+                .map(|h| {
+                    if s == 0 {
+                        format!("{:02}:{:02}", h, m)
+                    } else {
+                        format!("{:02}:{:02}:{:02}", h, m, s)
+                    }
+                })
+                .collect();
+            return TimePart::At(join_en(&times), times.join("、"));
+        }
+        (Some(0), Some(m), None) if hour == "*" => {
+            return TimePart::Repeat(
+                format!("every hour at minute {}", m),
+                format!("每小时第 {} 分", m),
+            );
+        }
+        (Some(0), Some(m), None) => {
+            if let Some(n) = step(hour) {
+                return TimePart::Repeat(
+                    format!("every {} hours at minute {}", n, m),
+                    format!("每 {} 小时的第 {} 分", n, m),
+                );
+            }
+        }
+        _ => {}
+    }
+
+    if hour == "*" {
+        if sec == "0" && min == "*" {
+            return TimePart::Repeat("every minute".into(), "每分钟".into());
+        }
+        if sec == "*" && min == "*" {
+            return TimePart::Repeat("every second".into(), "每秒".into());
+        }
+        if sec == "0"
+            && let Some(n) = step(min)
+        {
+            return TimePart::Repeat(format!("every {} minutes", n), format!("每 {} 分钟", n));
+        }
+        if min == "*"
+            && let Some(n) = step(sec)
+        {
+            return TimePart::Repeat(format!("every {} seconds", n), format!("每 {} 秒", n));
+        }
+    }
+
+    TimePart::Repeat(
+        format!("at second {}, minute {}, hour {}", sec, min, hour),
+        format!("在第 {} 秒、第 {} 分、第 {} 时", sec, min, hour),
+    )
+}
+
+fn cron_days(dom: &str, dow: &str) -> (String, String) {
+    let any = |field: &str| field == "*" || field == "?";
+    match (any(dom), any(dow)) {
+        (true, true) => ("every day".into(), "每天".into()),
+        (true, false) => {
+            let (en, zh) = weekday_field(dow);
+            (format!("on {}", en), format!("每{}", zh))
+        }
+        (false, true) => (
+            format!("on day {} of the month", dom),
+            format!("每月 {} 日", dom),
+        ),
+        (false, false) => {
+            let (en, zh) = weekday_field(dow);
+            (
+                format!("on day {} of the month and on {}", dom, en),
+                format!("每月 {} 日及每{}", dom, zh),
+            )
+        }
+    }
+}
+
+fn cron_months(month: &str) -> (String, String) {
+    if month == "*" || month == "?" {
+        return (String::new(), String::new());
+    }
+    let names: Vec<String> = month
+        .split(',')
+        .map(|item| match item.split_once('-') {
+            Some((from, to)) => format!("{} through {}", month_name(from), month_name(to)),
+            None => month_name(item),
+        })
+        .collect();
+    (format!(" in {}", join_en(&names)), format!("每年 {} 月", month.replace(',', "、")))
+}
+
+fn month_name(value: &str) -> String {
+    let upper = value.to_ascii_uppercase();
+    if let Ok(n) = upper.parse::<usize>()
+        && (1..=12).contains(&n)
+    {
+        return MONTHS[n - 1].1.to_string();
+    }
+    MONTHS
+        .iter()
+        .find(|(abbr, _)| upper.starts_with(abbr))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or(value.to_string())
+}
+
+/// WEEKDAYS 中的下标；数字按 cron 调度器约定 1=周日。
+/// 5 位表达式的数字星期（0=周日）已由 normalize_cron 换成名称，这里只会遇到 6 位写法
+fn weekday_index(value: &str) -> Option<usize> {
+    let upper = value.to_ascii_uppercase();
+    match upper.parse::<usize>() {
+        Ok(n) if (1..=7).contains(&n) => Some(n - 1),
+        Ok(_) => None,
+        Err(_) => WEEKDAYS.iter().position(|(abbr, _, _)| upper.starts_with(abbr)),
+    }
+}
+
+/// 带步长的星期项（如 "MON-FRI/2"、"*/2"）展开为具体的星期几
+fn weekday_steps(item: &str) -> Option<Vec<usize>> {
+    let (range, step) = item.split_once('/')?;
+    let step: usize = step.parse().ok().filter(|step| *step > 0)?;
+    let (from, to) = match range.split_once('-') {
+        _ if range == "*" => (0, WEEKDAYS.len() - 1),
+        Some((from, to)) => (weekday_index(from)?, weekday_index(to)?),
+        None => (weekday_index(range)?, WEEKDAYS.len() - 1),
+    };
+    Some((from..=to).step_by(step).collect())
+}
+
+fn weekday_name(value: &str) -> (String, String) {
+    match weekday_index(value) {
+        Some(i) => (WEEKDAYS[i].1.to_string(), WEEKDAYS[i].2.to_string()),
+        None => (value.to_string(), value.to_string()),
+    }
+}
+
+fn weekday_field(field: &str) -> (String, String) {
+    let (en, zh): (Vec<String>, Vec<String>) = field
+        .split(',')
+        .flat_map(|item| {
+            if let Some(days) = weekday_steps(item) {
+                return days
+                    .into_iter()
+                    .map(|i| (WEEKDAYS[i].1.to_string(), WEEKDAYS[i].2.to_string()))
+                    .collect();
+            }
+            vec![match item.split_once('-') {
+                Some((from, to)) => {
+                    let (from_en, from_zh) = weekday_name(from);
+                    let (to_en, to_zh) = weekday_name(to);
+                    (
+                        format!("{} through {}", from_en, to_en),
+                        format!("{}至{}", from_zh, to_zh),
+                    )
+                }
+                None => weekday_name(item),
+            }]
+        })
+        .unzip();
+    (join_en(&en), zh.join("、"))
+}
+
+// ---------- rrule ----------
+
+fn describe_rrule(text: &str) -> Description {
+    let mut params: Vec<(String, String)> = Vec::new();
+    let mut dtstart = None;
+    let mut exdates = 0;
+    for line in text.lines() {
+        let upper = line.to_ascii_uppercase();
+        if let Some(rule) = upper.strip_prefix("RRULE:") {
+            if params.is_empty() {
+                params = rule
+                    .split(';')
+                    .filter_map(|kv| kv.split_once('='))
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect();
+            }
+        } else if upper.starts_with("DTSTART") {
+            dtstart = line.split_once(':').map(|(_, v)| v.to_string());
+        } else if upper.starts_with("EXDATE") {
+            exdates += line
+                .split_once(':')
+                .map(|(_, v)| v.split(',').count())
+                .unwrap_or(0);
+        }
+    }
+    let get = |key: &str| {
+        params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let interval: u32 = get("INTERVAL").and_then(|v| v.parse().ok()).unwrap_or(1);
+    let (unit_en, unit_zh) = match get("FREQ").unwrap_or("") {
+        "YEARLY" => ("year", "年"),
+        "MONTHLY" => ("month", "个月"),
+        "WEEKLY" => ("week", "周"),
+        "DAILY" => ("day", "天"),
+        "HOURLY" => ("hour", "小时"),
+        "MINUTELY" => ("minute", "分钟"),
+        _ => ("second", "秒"),
+    };
+    let mut en = if interval == 1 {
+        format!("Every {}", unit_en)
+    } else {
+        format!("Every {} {}s", interval, unit_en)
+    };
+    let mut zh = if interval == 1 {
+        format!("每{}", unit_zh.trim_start_matches('个'))
+    } else {
+        format!("每 {} {}", interval, unit_zh)
+    };
+
+    let (days_en, days_zh): (Vec<String>, Vec<String>) = get("BYDAY")
+        .unwrap_or("")
+        .split(',')
+        .filter(|day| !day.is_empty())
+        .map(by_day)
+        .unzip();
+    if !days_en.is_empty() {
+        en.push_str(&format!(" on {}", join_en(&days_en)));
+        zh.push_str(&format!("的{}", days_zh.join("、")));
+    }
+    if let Some(days) = get("BYMONTHDAY") {
+        en.push_str(&format!(" on day {}", days));
+        zh.push_str(&format!(" {} 日", days));
+    }
+    if let Some(hours) = get("BYHOUR") {
+        let minute = get("BYMINUTE").unwrap_or("0");
+        let times: Vec<String> = hours
+            .split(',')
+            .flat_map(|h| {
+                minute
+                    .split(',')
+                    .map(move |m| format!("{:0>2}:{:0>2}", h, m))
+            })
+            .collect();
+        en.push_str(&format!(" at {}", join_en(&times)));
+        zh.push_str(&format!(" {}", times.join("、")));
+    }
+    if let Some(start) = dtstart {
+        en.push_str(&format!(", starting {}", start));
+        zh.push_str(&format!("，从 {} 开始", start));
+    }
+    if let Some(count) = get("COUNT") {
+        en.push_str(&format!(", {} times", count));
+        zh.push_str(&format!("，共 {} 次", count));
+    }
+    if let Some(until) = get("UNTIL") {
+        en.push_str(&format!(", until {}", until));
+        zh.push_str(&format!("，直到 {}", until));
+    }
+    if exdates > 0 {
+        en.push_str(&format!(", excluding {} date(s)", exdates));
+        zh.push_str(&format!("，排除 {} 个日期", exdates));
+    }
+    Description { en, zh }
+}
+
+/// BYDAY 取值，如 "MO"、"2TU"、"-1FR"
+fn by_day(value: &str) -> (String, String) {
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (ordinal, day) = value.split_at(split);
+    // BYDAY 的星期为两个字母，如 "SU"；无法识别时原样返回
+    let Some((_, day_en, day_zh)) = WEEKDAYS
+        .iter()
+        .find(|(abbr, _, _)| day.len() == 2 && abbr.starts_with(day))
+    else {
+        return (value.to_string(), value.to_string());
+    };
+
+    match ordinal.parse::<i32>() {
+        Ok(-1) => (format!("the last {}", day_en), format!("最后一个{}", day_zh)),
+        Ok(n) if n < 0 => (
+            format!("the {} last {}", ordinal_en(-n), day_en),
+            format!("倒数第 {} 个{}", -n, day_zh),
+        ),
+        Ok(n) => (
+            format!("the {} {}", ordinal_en(n), day_en),
+            format!("第 {} 个{}", n, day_zh),
+        ),
+        Err(_) => (day_en.to_string(), day_zh.to_string()),
+    }
+}
+
+fn ordinal_en(n: i32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (1, 11) | (2, 12) | (3, 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+fn join_en(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recurrence::normalize_cron;
+    use chrono::{Datelike, TimeZone};
+
+    fn cron(expr: &str) -> Description {
+        describe_cron(&normalize_cron(expr).unwrap())
+    }
+
+    fn rrule(rule: &str) -> Description {
+        describe_rrule(&format!("RRULE:{}", rule))
+    }
+
+    #[test]
+    fn describes_normalized_cron() {
+        let cases = [
+            ("0 9 * * 1-5", "At 09:00 on Monday through Friday (UTC)", "每周一至周五 09:00（UTC）"),
+            ("30 8 * * 0", "At 08:30 on Sunday (UTC)", "每周日 08:30（UTC）"),
+            ("0 9 * * 7", "At 09:00 on Sunday (UTC)", "每周日 09:00（UTC）"),
+            ("0 18 * * 0,6", "At 18:00 on Sunday and Saturday (UTC)", "每周日、周六 18:00（UTC）"),
+            ("15 10 * * 5", "At 10:15 on Friday (UTC)", "每周五 10:15（UTC）"),
+            (
+                "0 9 * * 1-5/2",
+                "At 09:00 on Monday, Wednesday and Friday (UTC)",
+                "每周一、周三、周五 09:00（UTC）",
+            ),
+            (
+                "0 9 * * */2",
+                "At 09:00 on Sunday, Tuesday, Thursday and Saturday (UTC)",
+                "每周日、周二、周四、周六 09:00（UTC）",
+            ),
+            ("*/15 * * * *", "Every 15 minutes (UTC)", "每 15 分钟（UTC）"),
+            ("* * * * *", "Every minute (UTC)", "每分钟（UTC）"),
+            ("0 */2 * * *", "Every 2 hours at minute 0 (UTC)", "每 2 小时的第 0 分（UTC）"),
+            ("0 9 1 * *", "At 09:00 on day 1 of the month (UTC)", "每月 1 日 09:00（UTC）"),
+            (
+                "0 9 1 1,6 *",
+                "At 09:00 on day 1 of the month in January and June (UTC)",
+                "每年 1、6 月 1 日 09:00（UTC）",
+            ),
+            // 6 位表达式中数字星期 1=周日
+            ("0 0 9 * * 1", "At 09:00 on Sunday (UTC)", "每周日 09:00（UTC）"),
+            ("0 0 9 * * 2-6", "At 09:00 on Monday through Friday (UTC)", "每周一至周五 09:00（UTC）"),
+        ];
+        for (expr, en, zh) in cases {
+            let description = cron(expr);
+            assert_eq!((description.en.as_str(), description.zh.as_str()), (en, zh), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn describes_cron_macros() {
+        let cases = [
+            ("@daily", "At 00:00 every day (UTC)"),
+            ("@weekly", "At 00:00 on Sunday (UTC)"),
+            ("@hourly", "Every hour at minute 0 (UTC)"),
+            ("@monthly", "At 00:00 on day 1 of the month (UTC)"),
+            ("@yearly", "At 00:00 on day 1 of the month in January (UTC)"),
+        ];
+        for (expr, en) in cases {
+            assert_eq!(cron(expr).en, en, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn weekday_descriptions_match_the_scheduler() {
+        // 标准 5 位写法 0-7，描述的星期须与调度器实际触发的星期一致
+        for day in 0..=7 {
+            let normalized = normalize_cron(&format!("0 9 * * {}", day)).unwrap();
+            let schedule: cron::Schedule = normalized.parse().unwrap();
+            let fires_on = schedule.upcoming(Utc).next().unwrap().weekday();
+            let expected = WEEKDAYS[fires_on.num_days_from_sunday() as usize].1;
+            let description = describe_cron(&normalized).en;
+            assert!(description.contains(expected), "{}: {} vs {}", day, description, expected);
+        }
+    }
+
+    #[test]
+    fn describes_rrule_by_day() {
+        let cases = [
+            ("FREQ=WEEKLY;BYDAY=MO,WE", "Every week on Monday and Wednesday", "每周的周一、周三"),
+            ("FREQ=MONTHLY;BYDAY=2TU", "Every month on the 2nd Tuesday", "每月的第 2 个周二"),
+            ("FREQ=MONTHLY;BYDAY=-1FR", "Every month on the last Friday", "每月的最后一个周五"),
+            ("FREQ=MONTHLY;BYDAY=-2SU", "Every month on the 2nd last Sunday", "每月的倒数第 2 个周日"),
+            (
+                "FREQ=DAILY;INTERVAL=2;BYHOUR=8;BYMINUTE=30;COUNT=5",
+                "Every 2 days at 08:30, 5 times",
+                "每 2 天 08:30，共 5 次",
+            ),
+        ];
+        for (rule, en, zh) in cases {
+            let description = rrule(rule);
+            assert_eq!((description.en.as_str(), description.zh.as_str()), (en, zh), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn empty_by_day_tokens_are_not_sunday() {
+        assert_eq!(rrule("FREQ=WEEKLY;BYDAY=,MO").en, "Every week on Monday");
+        assert_eq!(rrule("FREQ=WEEKLY;BYDAY=").en, "Every week");
+        assert_eq!(by_day("2").0, "2");
+        assert_eq!(by_day("S").0, "S");
+    }
+
+    #[test]
+    fn describes_intervals() {
+        let anchor = Utc.with_ymd_and_hms(2026, 10, 19, 0, 15, 0).unwrap();
+        let description = describe_interval(Duration::from_secs(5400), anchor);
+        assert_eq!(description.en, "Every 1 hour 30 minutes, aligned to 2026-10-19 00:15:00 UTC");
+        assert_eq!(description.zh, "每 1 小时 30 分钟，以 2026-10-19 00:15:00 UTC 为起点");
+    }
+}
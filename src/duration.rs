@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+// 时长上限 3650 天，避免换算成时间点时溢出
+const MAX_DURATION_SECS: u64 = 3650 * 24 * 60 * 60;
+
+/// 解析时长字符串，如 "90s"、"15m"、"2h"、"7d"、"1w"，也支持组合写法 "1h30m"
+pub fn parse_duration(input: &str) -> anyhow::Result<Duration> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(anyhow::anyhow!("Duration must not be empty"));
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Invalid duration '{}': unknown unit '{}', use s, m, h, d or w",
+                    input,
+                    c
+                ));
+            }
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid duration '{}': missing number before '{}'", input, c))?;
+        total = value
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| anyhow::anyhow!("Duration '{}' is too large", input))?;
+        number.clear();
+    }
+
+    if !number.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Invalid duration '{}': missing unit after {}",
+            input,
+            number
+        ));
+    }
+    if total == 0 {
+        return Err(anyhow::anyhow!("Duration '{}' must be greater than zero", input));
+    }
+    if total > MAX_DURATION_SECS {
+        return Err(anyhow::anyhow!(
+            "Duration '{}' is too large, maximum is 3650d",
+            input
+        ));
+    }
+    Ok(Duration::from_secs(total))
+}
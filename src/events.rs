@@ -0,0 +1,195 @@
+use axum::response::sse::Event as SseEvent;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+// 保留最近的事件数，用于断线重连时按 Last-Event-ID 补发
+const EVENT_HISTORY: usize = 1000;
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    JobCreated,
+    JobFired,
+    DeliverySucceeded,
+    DeliveryFailed,
+    JobCompleted,
+    JobCancelled,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::JobCreated => "job_created",
+            EventKind::JobFired => "job_fired",
+            EventKind::DeliverySucceeded => "delivery_succeeded",
+            EventKind::DeliveryFailed => "delivery_failed",
+            EventKind::JobCompleted => "job_completed",
+            EventKind::JobCancelled => "job_cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    pub timestamp: DateTime<Utc>,
+    // 立即推送（/notify）的事件没有任务 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    // 任务或推送所属的令牌 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+}
+
+/// 事件筛选条件，均为空时接收全部事件
+#[derive(Debug, Default, Deserialize)]
+pub struct EventFilter {
+    pub job_id: Option<String>,
+    pub tag: Option<String>,
+    pub owner: Option<String>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Event) -> bool {
+        self.job_id
+            .as_ref()
+            .is_none_or(|id| event.job_id.as_ref() == Some(id))
+            && self.tag.as_ref().is_none_or(|tag| event.tags.contains(tag))
+            && self
+                .owner
+                .as_ref()
+                .is_none_or(|owner| event.owner.as_ref() == Some(owner))
+    }
+}
+
+fn to_sse(event: &Event) -> SseEvent {
+    SseEvent::default()
+        .id(event.id.to_string())
+        .event(event.kind.as_str())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
+
+/// 任务与推送事件的广播通道
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+    next_id: AtomicU64,
+    history: Mutex<VecDeque<Event>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            next_id: AtomicU64::new(1),
+            history: Mutex::new(VecDeque::with_capacity(EVENT_HISTORY)),
+        }
+    }
+
+    pub fn publish(
+        &self,
+        kind: EventKind,
+        job_id: Option<&str>,
+        owner: Option<&str>,
+        tags: &[String],
+        data: Value,
+    ) {
+        // 持锁写入历史再广播，保证订阅时补发的历史与实时事件不重不漏
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let event = Event {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            kind,
+            timestamp: Utc::now(),
+            job_id: job_id.map(str::to_string),
+            owner: owner.map(str::to_string),
+            tags: tags.to_vec(),
+            data,
+        };
+        if history.len() == EVENT_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(event.clone());
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.sender.send(event);
+    }
+
+    /// 只订阅实时事件
+    pub fn receiver(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// 订阅实时事件，同时返回 last_id 之后的历史事件。
+    /// last_id 比当前最新事件还大（服务重启过）时补发全部历史
+    fn subscribe(&self, last_id: Option<u64>) -> (Vec<Event>, broadcast::Receiver<Event>) {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.sender.subscribe();
+        let backlog = match last_id {
+            None => Vec::new(),
+            Some(last_id) => {
+                let latest = history.back().map_or(0, |event| event.id);
+                let from = if last_id > latest { 0 } else { last_id };
+                history.iter().filter(|event| event.id > from).cloned().collect()
+            }
+        };
+        (backlog, receiver)
+    }
+
+    /// 按筛选条件生成 SSE 事件流：先补发 last_id 之后的历史，再转发实时事件
+    pub fn stream(
+        &self,
+        filter: EventFilter,
+        last_id: Option<u64>,
+    ) -> impl Stream<Item = Result<SseEvent, Infallible>> + use<> {
+        let (backlog, receiver) = self.subscribe(last_id);
+        let filter = Arc::new(filter);
+
+        let backlog = {
+            let filter = Arc::clone(&filter);
+            stream::iter(
+                backlog
+                    .into_iter()
+                    .filter(move |event| filter.matches(event))
+                    .map(|event| Ok(to_sse(&event))),
+            )
+        };
+        let live = stream::unfold(receiver, move |mut receiver| {
+            let filter = Arc::clone(&filter);
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) if filter.matches(&event) => {
+                            return Some((Ok(to_sse(&event)), receiver));
+                        }
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Event stream lagged, {} events skipped", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+        backlog.chain(live)
+    }
+}
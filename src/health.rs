@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::notify::Notifier;
+use crate::scheduler::NotificationScheduler;
+
+// 探测 Bark 与读取任务列表的超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const STORE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadyStatus {
+    Ready,
+    Degraded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub ok: bool,
+    pub checked_at: DateTime<Utc>,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchedulerCheck {
+    pub running: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobStoreCheck {
+    pub available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BarkCheck {
+    pub last_success_at: Option<DateTime<Utc>>,
+    // 未开启 ready_probe 时不探测
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe: Option<ProbeResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub status: ReadyStatus,
+    pub scheduler: SchedulerCheck,
+    pub job_store: JobStoreCheck,
+    pub bark: BarkCheck,
+}
+
+/// 就绪检查：调度器、任务列表和 Bark 可达性，探测结果缓存 ttl 时长
+pub struct Readiness {
+    probe: bool,
+    ttl: Duration,
+    // 持锁探测，并发请求共用同一次探测结果
+    cache: Mutex<Option<(Instant, ProbeResult)>>,
+}
+
+impl Readiness {
+    pub fn new(probe: bool, ttl: Duration) -> Self {
+        Self {
+            probe,
+            ttl,
+            cache: Mutex::new(None),
+        }
+    }
+
+    pub async fn check(
+        &self,
+        scheduler: &NotificationScheduler,
+        notifier: &Notifier,
+    ) -> ReadinessReport {
+        let running = scheduler.is_running().await;
+        let jobs = scheduler.job_count(STORE_TIMEOUT).await;
+        let probe = if self.probe {
+            Some(self.probe_bark(notifier).await)
+        } else {
+            None
+        };
+
+        let healthy = running && jobs.is_some() && probe.as_ref().is_none_or(|probe| probe.ok);
+        ReadinessReport {
+            status: if healthy {
+                ReadyStatus::Ready
+            } else {
+                ReadyStatus::Degraded
+            },
+            scheduler: SchedulerCheck { running },
+            job_store: JobStoreCheck {
+                available: jobs.is_some(),
+                jobs,
+            },
+            bark: BarkCheck {
+                last_success_at: notifier.last_success(),
+                probe,
+            },
+        }
+    }
+
+    async fn probe_bark(&self, notifier: &Notifier) -> ProbeResult {
+        let mut cache = self.cache.lock().await;
+        if let Some((at, result)) = cache.as_ref()
+            && at.elapsed() < self.ttl
+        {
+            return result.clone();
+        }
+
+        let started = Instant::now();
+        let error = notifier.ping(PROBE_TIMEOUT).await.err();
+        let result = ProbeResult {
+            ok: error.is_none(),
+            checked_at: Utc::now(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: error.map(|e| e.to_string()),
+        };
+        *cache = Some((Instant::now(), result.clone()));
+        result
+    }
+}
@@ -0,0 +1,202 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::api::ApiResponse;
+use crate::tokens::Principal;
+
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+pub const REPLAYED_HEADER: &str = "idempotency-replayed";
+
+// 请求体上限，与 axum Json 提取器默认值一致
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+enum Entry {
+    // 首个请求仍在处理中
+    Pending {
+        fingerprint: u64,
+        created: Instant,
+    },
+    Done {
+        fingerprint: u64,
+        created: Instant,
+        status: StatusCode,
+        content_type: Option<HeaderValue>,
+        body: Bytes,
+    },
+}
+
+impl Entry {
+    fn created(&self) -> Instant {
+        match self {
+            Entry::Pending { created, .. } | Entry::Done { created, .. } => *created,
+        }
+    }
+
+    fn fingerprint(&self) -> u64 {
+        match self {
+            Entry::Pending { fingerprint, .. } | Entry::Done { fingerprint, .. } => *fingerprint,
+        }
+    }
+}
+
+/// 幂等键存储：键 → 首次请求的响应，在有效期内重放
+pub struct IdempotencyStore {
+    entries: Mutex<HashMap<String, Entry>>,
+    ttl: Duration,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+}
+
+/// 幂等中间件：识别 Idempotency-Key 请求头或请求体中的 client_job_id，
+/// 相同键相同请求体直接返回首次结果，请求体不同返回 409
+pub async fn idempotency_middleware(
+    State(store): State<Arc<IdempotencyStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response(),
+    };
+
+    let key = parts
+        .headers
+        .get(IDEMPOTENCY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .or_else(|| client_job_id(&bytes));
+
+    let Some(key) = key else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    // 不同令牌、不同接口使用同一个键互不影响
+    let principal = parts
+        .extensions
+        .get::<Principal>()
+        .map_or("", |principal| principal.id.as_str());
+    let scoped_key = format!("{} {} {} {}", principal, parts.method, parts.uri.path(), key);
+    let fingerprint = fingerprint(&bytes);
+
+    {
+        let mut entries = store.entries.lock().await;
+        entries.retain(|_, entry| entry.created().elapsed() < store.ttl);
+
+        match entries.get(&scoped_key) {
+            Some(entry) if entry.fingerprint() != fingerprint => {
+                return conflict("Idempotency key was already used with a different request body");
+            }
+            Some(Entry::Pending { .. }) => {
+                return conflict("A request with this idempotency key is still in progress");
+            }
+            Some(Entry::Done {
+                status,
+                content_type,
+                body,
+                ..
+            }) => {
+                info!("Replaying response for idempotency key {}", key);
+                let mut response = (*status, body.clone()).into_response();
+                if let Some(content_type) = content_type {
+                    response
+                        .headers_mut()
+                        .insert(header::CONTENT_TYPE, content_type.clone());
+                }
+                response
+                    .headers_mut()
+                    .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+                return response;
+            }
+            None => {
+                entries.insert(
+                    scoped_key.clone(),
+                    Entry::Pending {
+                        fingerprint,
+                        created: Instant::now(),
+                    },
+                );
+            }
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => {
+            store.entries.lock().await.remove(&scoped_key);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // 只保存成功结果，失败的请求允许客户端用同一个键重试
+    let mut entries = store.entries.lock().await;
+    if parts.status.is_success() && is_success_body(&body) {
+        entries.insert(
+            scoped_key,
+            Entry::Done {
+                fingerprint,
+                created: Instant::now(),
+                status: parts.status,
+                content_type: parts.headers.get(header::CONTENT_TYPE).cloned(),
+                body: body.clone(),
+            },
+        );
+    } else {
+        entries.remove(&scoped_key);
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn client_job_id(body: &[u8]) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    value
+        .get("client_job_id")?
+        .as_str()
+        .map(str::to_string)
+        .filter(|id| !id.is_empty())
+}
+
+fn fingerprint(body: &[u8]) -> u64 {
+    // 按 JSON 语义比较，忽略字段顺序和空白差异
+    let mut hasher = DefaultHasher::new();
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(value) => value.to_string().hash(&mut hasher),
+        Err(_) => body.hash(&mut hasher),
+    }
+    hasher.finish()
+}
+
+fn is_success_body(body: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| value.get("success").and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
+fn conflict(msg: &str) -> Response {
+    (StatusCode::CONFLICT, Json(ApiResponse::<()>::error(msg))).into_response()
+}
@@ -1,18 +1,56 @@
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 mod api;
+mod audit;
 mod auth;
+mod bulk;
+mod callback;
+mod condition;
 mod config;
+mod content;
+mod describe;
+mod duration;
+mod events;
+mod health;
+mod idempotency;
+mod metrics;
+mod natural;
+mod network;
 mod notify;
+mod outbound;
+mod ratelimit;
+mod recurrence;
 mod scheduler;
+mod signing;
+mod template;
+mod tls;
+mod tokens;
+mod unix;
 
 use api::{create_router, AppState};
-use auth::AuthState;
-use config::AppConfig;
+use audit::AuditLog;
+use auth::{AuthState, FailureTracker};
+use callback::CallbackDispatcher;
+use config::{AppConfig, Listen};
+use events::EventBus;
+use health::Readiness;
+use idempotency::IdempotencyStore;
+use network::NetworkPolicy;
 use notify::Notifier;
+use outbound::OutboundPolicy;
+use ratelimit::RateLimiter;
 use scheduler::NotificationScheduler;
+use signing::SignatureVerifier;
+use template::TemplateStore;
+use tls::TlsConfig;
+use tokens::TokenRegistry;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -38,33 +76,132 @@ async fn main() -> anyhow::Result<()> {
     // Create notifier
     let notifier = Arc::new(Notifier::new(
         config.bark_url.clone(),
-        config.device_key.clone(),
+        config.device_keys(),
     ));
 
+    // Job and delivery events, shared by the scheduler and the /events stream
+    let events = Arc::new(EventBus::new());
+
+    // Hosts that content sources, condition probes and callbacks may reach
+    let outbound = Arc::new(OutboundPolicy::new(config.outbound_allowed_networks.clone()));
+
+    // Outbound callbacks for jobs with a callback_url
+    let callbacks = Arc::new(CallbackDispatcher::new(
+        Some(config.callback_secret.clone()),
+        Arc::clone(&outbound),
+    )?);
+    callbacks.start(&events);
+
     // Create and start scheduler
-    let scheduler = Arc::new(NotificationScheduler::new(Arc::clone(&notifier)).await?);
+    let scheduler = Arc::new(
+        NotificationScheduler::new(
+            Arc::clone(&notifier),
+            Arc::clone(&events),
+            Arc::clone(&callbacks),
+            outbound,
+        )
+        .await?,
+    );
     scheduler.start().await?;
 
+    // API tokens from config; more can be issued via /admin/tokens
+    let tokens = Arc::new(TokenRegistry::from_config(
+        &config.password,
+        &config.tokens,
+        &config.token_store,
+    )?);
+    if tokens.is_empty().await {
+        tracing::warn!("No password or tokens configured, API is open to everyone");
+    } else {
+        info!("Loaded {} API tokens", tokens.len().await);
+    }
+
     // Create app state
     let state = AppState {
         notifier: Arc::clone(&notifier),
         scheduler: Arc::clone(&scheduler),
+        templates: Arc::new(TemplateStore::new()),
+        idempotency: Arc::new(IdempotencyStore::new(Duration::from_secs(
+            config.idempotency_ttl_secs,
+        ))),
+        events,
+        callbacks,
+        readiness: Arc::new(Readiness::new(
+            config.ready_probe,
+            Duration::from_secs(config.ready_probe_ttl_secs),
+        )),
+        tokens: Arc::clone(&tokens),
+        limiter: Arc::new(RateLimiter::new(config.rate_limit.clone())),
+        network: Arc::new(NetworkPolicy::new(
+            config.allowed_networks.clone(),
+            config.trusted_proxies.clone(),
+        )),
+        audit: Arc::new(AuditLog::new(
+            &config.audit_log,
+            config.audit_max_bytes,
+            config.audit_max_files,
+        )?),
     };
 
     // Auth state
     let auth_state = AuthState {
-        password: config.password.clone(),
+        tokens: Arc::clone(&tokens),
+        failures: Arc::new(FailureTracker::new(
+            config.auth_max_failures,
+            Duration::from_secs(config.auth_lockout_secs),
+        )),
+        signatures: Arc::new(SignatureVerifier::new(Duration::from_secs(
+            config.signature_window_secs,
+        ))),
+        allow_query_token: config.allow_query_token,
     };
 
     // Create router with auth middleware
     let app = create_router(state, auth_state);
 
-    // Start server
-    let addr = config.socket_addr();
-    info!("Server listening on http://{}", addr);
-    
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Start servers; a Unix socket can run alongside TCP
+    let listen = config.listen()?;
+    let mut servers = JoinSet::new();
+    let tcp_addr = match &listen {
+        Listen::Tcp(addr) => Some(*addr),
+        Listen::Unix(path) => {
+            let listener = unix::bind(path, config.socket_mode, &config.socket_owner)?;
+            info!("Server listening on unix:{}", path.display());
+            servers.spawn(unix::serve(listener, app.clone()));
+            config.keep_tcp.then(|| config.socket_addr())
+        }
+    };
+    if let Some(addr) = tcp_addr {
+        servers.spawn(serve_tcp(addr, config.tls.clone(), app));
+    }
+
+    // 任一监听退出即结束进程
+    if let Some(result) = servers.join_next().await {
+        result??;
+    }
 
     Ok(())
 }
+
+async fn serve_tcp(addr: SocketAddr, tls: Option<TlsConfig>, app: Router) -> anyhow::Result<()> {
+    // 保留连接地址，供认证失败按 IP 计数
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match tls {
+        Some(tls) => {
+            let rustls = RustlsConfig::from_config(tls::server_config(&tls)?);
+            tls::watch(tls.clone(), rustls.clone());
+            if tls.mutual() {
+                info!("Server listening on https://{} (client certificates required)", addr);
+            } else {
+                info!("Server listening on https://{}", addr);
+            }
+            axum_server::bind_rustls(addr, rustls).serve(service).await?;
+        }
+        None => {
+            info!("Server listening on http://{}", addr);
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            axum::serve(listener, service).await?;
+        }
+    }
+    Ok(())
+}
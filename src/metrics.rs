@@ -0,0 +1,146 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+
+/// 进程内的指标，与 tracing 一样全局共享
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub const NOTIFICATIONS_SENT: &str = "bark_notifications_sent_total";
+pub const NOTIFICATIONS_FAILED: &str = "bark_notifications_failed_total";
+pub const BARK_RESPONSES: &str = "bark_responses_total";
+pub const JOB_FIRES: &str = "bark_job_fires_total";
+pub const CALLBACK_RETRIES: &str = "bark_callback_retries_total";
+pub const HTTP_REQUESTS: &str = "bark_http_requests_total";
+pub const RATE_LIMITED: &str = "bark_rate_limited_total";
+pub const ACTIVE_JOBS: &str = "bark_active_jobs";
+pub const BARK_LATENCY: &str = "bark_request_duration_seconds";
+
+const COUNTERS: [(&str, &str); 7] = [
+    (NOTIFICATIONS_SENT, "Notifications delivered to Bark, by channel and device"),
+    (NOTIFICATIONS_FAILED, "Notifications that failed to deliver, by channel and device"),
+    (BARK_RESPONSES, "Bark responses by code (HTTP status or Bark code, \"error\" for transport failures)"),
+    (JOB_FIRES, "Scheduled job executions, by job type"),
+    (CALLBACK_RETRIES, "Callback webhook delivery retries"),
+    (HTTP_REQUESTS, "HTTP requests handled, by method, route and status"),
+    (RATE_LIMITED, "Requests rejected by rate limiting, by limit (ip, token, route, device)"),
+];
+
+// Bark 请求耗时直方图的桶（秒）
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+struct Histogram {
+    counts: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+pub struct Metrics {
+    // 指标名 -> 标签 -> 计数
+    counters: Mutex<BTreeMap<&'static str, BTreeMap<String, u64>>>,
+    latency: Mutex<Histogram>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            counters: Mutex::new(BTreeMap::new()),
+            latency: Mutex::new(Histogram {
+                counts: [0; LATENCY_BUCKETS.len()],
+                sum: 0.0,
+                count: 0,
+            }),
+        }
+    }
+
+    pub fn inc(&self, name: &'static str, labels: &[(&str, &str)]) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        *counters
+            .entry(name)
+            .or_default()
+            .entry(format_labels(labels))
+            .or_default() += 1;
+    }
+
+    pub fn observe_latency(&self, seconds: f64) {
+        let mut histogram = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        for (bucket, count) in LATENCY_BUCKETS.iter().zip(histogram.counts.iter_mut()) {
+            if seconds <= *bucket {
+                *count += 1;
+            }
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+
+    /// 输出 Prometheus 文本格式；gauges 为抓取时计算的 (标签, 值)
+    pub fn render(&self, active_jobs: &[(&str, usize)]) -> String {
+        let mut out = String::new();
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        for (name, help) in COUNTERS {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (labels, value) in counters.get(name).into_iter().flatten() {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        }
+        drop(counters);
+
+        let _ = writeln!(out, "# HELP {} Active (not paused) jobs, by type", ACTIVE_JOBS);
+        let _ = writeln!(out, "# TYPE {} gauge", ACTIVE_JOBS);
+        for (kind, value) in active_jobs {
+            let _ = writeln!(out, "{}{} {}", ACTIVE_JOBS, format_labels(&[("type", kind)]), value);
+        }
+
+        let histogram = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(out, "# HELP {} Latency of requests to the Bark server", BARK_LATENCY);
+        let _ = writeln!(out, "# TYPE {} histogram", BARK_LATENCY);
+        for (bucket, count) in LATENCY_BUCKETS.iter().zip(histogram.counts.iter()) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", BARK_LATENCY, bucket, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", BARK_LATENCY, histogram.count);
+        let _ = writeln!(out, "{}_sum {}", BARK_LATENCY, histogram.sum);
+        let _ = writeln!(out, "{}_count {}", BARK_LATENCY, histogram.count);
+        out
+    }
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// 统计 HTTP 请求数，路由取匹配的路径模板（如 /jobs/:job_id），避免标签基数过大
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched".to_string(), |path| path.as_str().to_string());
+    let response = next.run(request).await;
+    METRICS.inc(
+        HTTP_REQUESTS,
+        &[
+            ("method", &method),
+            ("route", &route),
+            ("status", response.status().as_str()),
+        ],
+    );
+    response
+}
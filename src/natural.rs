@@ -0,0 +1,1059 @@
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::recurrence::{parse_rrule, rrule_next_after};
+use crate::scheduler::{JobKind, ScheduleSpec};
+
+/// 解析结果的可信程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParsedTime {
+    // once、rrule 或 interval
+    #[serde(rename = "type")]
+    pub kind: JobKind,
+    // 一次性时间；循环规则时为第一次触发时间
+    pub at: DateTime<Utc>,
+    // at 在指定时区的本地时间
+    pub local: String,
+    pub timezone: String,
+    // 循环规则，可直接用于 /schedule/cron
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScheduleSpec>,
+    pub confidence: Confidence,
+    // 存在多种合理理解时为 true，notes 中说明采用了哪一种
+    pub ambiguous: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 解析 IANA 时区名，未指定时使用 UTC
+pub fn parse_timezone(name: Option<&str>) -> anyhow::Result<Tz> {
+    match name {
+        None => Ok(Tz::UTC),
+        Some(name) => name
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Unknown timezone '{}'", name)),
+    }
+}
+
+/// 以 reference 为当前时间、在 tz 时区内解析中英文时间短语，
+/// 如 "明天早上九点"、"next Friday at 3pm"、"in 2 hours"、"每个工作日 9:30"
+pub fn parse(input: &str, reference: DateTime<Utc>, tz: Tz) -> anyhow::Result<ParsedTime> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(anyhow::anyhow!("Time phrase must not be empty"));
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(trimmed) {
+        let at = at.with_timezone(&Utc);
+        return Ok(ParsedTime {
+            kind: JobKind::Once,
+            at,
+            local: at.with_timezone(&tz).to_rfc3339(),
+            timezone: tz.name().to_string(),
+            schedule: None,
+            confidence: Confidence::High,
+            ambiguous: false,
+            notes: Vec::new(),
+        });
+    }
+
+    let today = reference.with_timezone(&tz).date_naive();
+    let mut scan = Scanner::new(&normalize(trimmed));
+    let mut parts = Parts::default();
+    take_repeat(&mut scan, &mut parts, today)?;
+    if parts.repeat.is_none() {
+        take_offset(&mut scan, &mut parts)?;
+    }
+    take_date(&mut scan, &mut parts, today);
+    take_period(&mut scan, &mut parts);
+    take_time(&mut scan, &mut parts);
+
+    if parts.repeat.is_none() && parts.offset.is_none() && parts.date.is_none() && parts.time.is_none()
+    {
+        return Err(anyhow::anyhow!(
+            "Could not recognise a date or time in '{}'",
+            trimmed
+        ));
+    }
+    let leftover = scan.leftover();
+    if !leftover.is_empty() {
+        parts.notes.push(format!("Ignored unrecognised text: '{}'", leftover));
+    }
+
+    let mut parsed = match parts.repeat.take() {
+        Some(repeat) => resolve_repeat(repeat, &mut parts, reference, today, tz)?,
+        None => resolve_once(&mut parts, reference, today, tz)?,
+    };
+
+    let mut confidence = Confidence::High;
+    if parts.defaulted || !leftover.is_empty() {
+        confidence = Confidence::Medium;
+    }
+    if parts.ambiguous {
+        confidence = Confidence::Low;
+    }
+    if parsed.kind == JobKind::Once && parsed.at <= reference {
+        parts.notes.push("Resolved time is not in the future".to_string());
+        confidence = Confidence::Low;
+    }
+    parsed.confidence = confidence;
+    parsed.ambiguous = parts.ambiguous;
+    parsed.notes = parts.notes;
+    Ok(parsed)
+}
+
+// ---------- 中间结果 ----------
+
+enum Repeat {
+    Daily(u32),
+    Weekly(u32, Vec<Weekday>),
+    Monthly(u32),
+    // 固定间隔，如 (2, 'h')
+    Every(u32, char),
+}
+
+#[derive(Clone, Copy)]
+enum Period {
+    EarlyMorning,
+    Morning,
+    Noon,
+    Afternoon,
+    Evening,
+    Night,
+}
+
+// 解析出的时间已过去时向后顺延的单位
+#[derive(Clone, Copy)]
+enum Roll {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+struct Clock {
+    hour: u32,
+    minute: u32,
+    // 已通过 am/pm 或 24 小时制写法确定上下午
+    exact: bool,
+    midnight: bool,
+}
+
+#[derive(Default)]
+struct Parts {
+    repeat: Option<Repeat>,
+    offset: Option<Duration>,
+    date: Option<NaiveDate>,
+    roll: Option<Roll>,
+    period: Option<Period>,
+    time: Option<Clock>,
+    notes: Vec<String>,
+    ambiguous: bool,
+    defaulted: bool,
+}
+
+struct Scanner {
+    text: String,
+}
+
+impl Scanner {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+        }
+    }
+
+    /// 匹配成功时返回各捕获组（未参与匹配的组为空字符串），并从文本中移除匹配部分
+    fn take(&mut self, re: &Regex) -> Option<Vec<String>> {
+        self.take_if(re, |_| true)
+    }
+
+    /// 同 take，但只有 accept 接受各捕获组时才移除匹配部分
+    fn take_if(&mut self, re: &Regex, accept: impl Fn(&[String]) -> bool) -> Option<Vec<String>> {
+        let caps = re.captures(&self.text)?;
+        let groups: Vec<String> = caps
+            .iter()
+            .map(|m| m.map_or(String::new(), |m| m.as_str().to_string()))
+            .collect();
+        if !accept(&groups) {
+            return None;
+        }
+        let range = caps.get(0)?.range();
+        self.text.replace_range(range, " ");
+        Some(groups)
+    }
+
+    /// 去掉连接词和标点后剩余的未识别文本
+    fn leftover(&self) -> String {
+        const FILLERS: [&str; 14] = [
+            "at", "on", "the", "of", "and", "in", "by", "for", "this", "please", "remind", "me",
+            "to", "o'clock",
+        ];
+        self.text
+            .split(|c: char| c.is_whitespace() || ",.，。、;；!！的在于".contains(c))
+            .filter(|word| !word.is_empty() && !FILLERS.contains(word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+macro_rules! regex {
+    ($name:ident, $pattern:expr) => {
+        static $name: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new($pattern).expect("invalid built-in regex"));
+    };
+}
+
+// ---------- 预处理 ----------
+
+regex!(
+    RE_EN_NUMBER,
+    r"\b(one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve|fifteen|twenty|thirty|forty|fifty)\b"
+);
+
+fn normalize(input: &str) -> String {
+    let text = input
+        .to_lowercase()
+        .replace(['：', ':'], ":")
+        .replace('，', ",")
+        .replace('點', "点");
+    let text = RE_EN_NUMBER.replace_all(&text, |caps: &regex::Captures| {
+        match &caps[1] {
+            "one" => "1",
+            "two" => "2",
+            "three" => "3",
+            "four" => "4",
+            "five" => "5",
+            "six" => "6",
+            "seven" => "7",
+            "eight" => "8",
+            "nine" => "9",
+            "ten" => "10",
+            "eleven" => "11",
+            "twelve" => "12",
+            "fifteen" => "15",
+            "twenty" => "20",
+            "thirty" => "30",
+            "forty" => "40",
+            _ => "50",
+        }
+        .to_string()
+    });
+    chinese_numerals(&text)
+}
+
+/// 将中文数字换成阿拉伯数字："二十三" → "23"，"二零二六" → "2026"，"两" → "2"
+fn chinese_numerals(text: &str) -> String {
+    fn digit(c: char) -> Option<u32> {
+        "零一二三四五六七八九"
+            .chars()
+            .position(|d| d == c)
+            .map(|n| n as u32)
+            .or(match c {
+                '〇' => Some(0),
+                '两' => Some(2),
+                _ => None,
+            })
+    }
+    fn convert(run: &str) -> String {
+        match run.split_once('十') {
+            Some((tens, ones)) => {
+                let tens = tens.chars().next().and_then(digit).unwrap_or(1);
+                let ones = ones.chars().next().and_then(digit).unwrap_or(0);
+                (tens * 10 + ones).to_string()
+            }
+            None => run
+                .chars()
+                .filter_map(digit)
+                .map(|d| char::from_digit(d, 10).unwrap_or('0'))
+                .collect(),
+        }
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut run = String::new();
+    for c in text.chars() {
+        if c == '十' || digit(c).is_some() {
+            run.push(c);
+            continue;
+        }
+        if !run.is_empty() {
+            out.push_str(&convert(&run));
+            run.clear();
+        }
+        out.push(c);
+    }
+    if !run.is_empty() {
+        out.push_str(&convert(&run));
+    }
+    out
+}
+
+fn weekday_from(name: &str) -> Option<Weekday> {
+    let weekday = match name {
+        "1" => Weekday::Mon,
+        "2" => Weekday::Tue,
+        "3" => Weekday::Wed,
+        "4" => Weekday::Thu,
+        "5" => Weekday::Fri,
+        "6" => Weekday::Sat,
+        "日" | "天" | "7" => Weekday::Sun,
+        _ if name.starts_with("mon") => Weekday::Mon,
+        _ if name.starts_with("tue") => Weekday::Tue,
+        _ if name.starts_with("wed") => Weekday::Wed,
+        _ if name.starts_with("thu") => Weekday::Thu,
+        _ if name.starts_with("fri") => Weekday::Fri,
+        _ if name.starts_with("sat") => Weekday::Sat,
+        _ if name.starts_with("sun") => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+fn month_from(name: &str) -> Option<u32> {
+    const NAMES: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    NAMES
+        .iter()
+        .position(|abbr| name.starts_with(abbr))
+        .map(|i| i as u32 + 1)
+}
+
+// ---------- 循环规则 ----------
+
+const EN_WEEKDAY: &str =
+    r"(?:monday|mon|tuesday|tues|tue|wednesday|wed|thursday|thurs|thur|thu|friday|fri|saturday|sat|sunday|sun)";
+
+regex!(
+    RE_EVERY_N,
+    r"(?:\b(?:every|each)\s+(\d+|other)\s*(seconds?|secs?|minutes?|mins?|hours?|hrs?|days?|weeks?)\b|每隔?(\d+)个?(秒钟?|分钟|小时|钟头|天|日|周|星期|礼拜))"
+);
+regex!(RE_EVERY_HALF_HOUR, r"每隔?半个?小时|\bevery\s+half\s+(?:an\s+)?hour\b");
+regex!(
+    RE_WORKDAYS,
+    r"\b(?:every|each|on)\s+(?:weekday|workday|business\s+day)s?\b|\bweekdays\b|每个?工作日|工作日"
+);
+regex!(RE_WEEKENDS, r"\b(?:every|each|on)\s+weekends?\b|每个?周末");
+static RE_EVERY_WEEKDAYS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"\b(?:every|each)\s+({w}(?:\s*(?:,|and|&)\s*{w})*)\b|每个?(?:周|星期|礼拜)([1-6日天](?:[、,和及]?[1-6日天])*)",
+        w = EN_WEEKDAY
+    ))
+    .expect("invalid built-in regex")
+});
+regex!(RE_EN_WEEKDAY_NAME, EN_WEEKDAY);
+regex!(
+    RE_MONTHLY_DAY,
+    r"每个?月(\d{1,2})[号日]|\b(?:every|each)\s+month\s+on\s+the\s+(\d{1,2})(?:st|nd|rd|th)?\b|\b(?:on\s+)?the\s+(\d{1,2})(?:st|nd|rd|th)?\s+of\s+(?:every|each)\s+month\b|\bmonthly\s+on\s+the\s+(\d{1,2})(?:st|nd|rd|th)?\b"
+);
+regex!(RE_DAILY, r"\b(?:every\s*day|each\s+day|daily)\b|每天|每日|天天");
+regex!(RE_HOURLY, r"\b(?:every|each)\s+hour\b|\bhourly\b|每个?小时");
+regex!(RE_MINUTELY, r"\b(?:every|each)\s+minute\b|每分钟");
+regex!(RE_WEEKLY, r"\b(?:every|each)\s+week\b|\bweekly\b|每个?(?:周|星期|礼拜)");
+regex!(RE_MONTHLY, r"\b(?:every|each)\s+month\b|\bmonthly\b|每个?月");
+
+fn take_repeat(scan: &mut Scanner, parts: &mut Parts, today: NaiveDate) -> anyhow::Result<()> {
+    let repeat = if let Some(caps) = scan.take(&RE_EVERY_N) {
+        let (amount, unit) = if caps[1].is_empty() {
+            (caps[3].as_str(), caps[4].as_str())
+        } else {
+            (caps[1].as_str(), caps[2].as_str())
+        };
+        let amount = if amount == "other" {
+            2
+        } else {
+            amount
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Repeat interval {} is too large", amount))?
+        };
+        let unit = match unit.chars().next() {
+            Some('s' | '秒') => 's',
+            Some('m' | '分') => 'm',
+            Some('h' | '小' | '钟') => 'h',
+            Some('d' | '天' | '日') => 'd',
+            _ => 'w',
+        };
+        Repeat::Every(amount, unit)
+    } else if scan.take(&RE_EVERY_HALF_HOUR).is_some() {
+        Repeat::Every(30, 'm')
+    } else if scan.take(&RE_WORKDAYS).is_some() {
+        Repeat::Weekly(
+            1,
+            vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+        )
+    } else if scan.take(&RE_WEEKENDS).is_some() {
+        Repeat::Weekly(1, vec![Weekday::Sat, Weekday::Sun])
+    } else if let Some(caps) = scan.take(&RE_EVERY_WEEKDAYS) {
+        let days = if caps[1].is_empty() {
+            caps[2]
+                .chars()
+                .filter_map(|c| weekday_from(&c.to_string()))
+                .collect()
+        } else {
+            RE_EN_WEEKDAY_NAME
+                .find_iter(&caps[1])
+                .filter_map(|m| weekday_from(m.as_str()))
+                .collect()
+        };
+        Repeat::Weekly(1, days)
+    } else if let Some(caps) = scan.take(&RE_MONTHLY_DAY) {
+        let day = caps[1..]
+            .iter()
+            .find(|group| !group.is_empty())
+            .and_then(|day| day.parse().ok())
+            .unwrap_or(1);
+        Repeat::Monthly(day)
+    } else if scan.take(&RE_DAILY).is_some() {
+        Repeat::Daily(1)
+    } else if scan.take(&RE_HOURLY).is_some() {
+        Repeat::Every(1, 'h')
+    } else if scan.take(&RE_MINUTELY).is_some() {
+        Repeat::Every(1, 'm')
+    } else if scan.take(&RE_WEEKLY).is_some() {
+        // 未写星期几时按今天所在的星期几
+        Repeat::Weekly(1, vec![today.weekday()])
+    } else if scan.take(&RE_MONTHLY).is_some() {
+        Repeat::Monthly(today.day())
+    } else {
+        return Ok(());
+    };
+    parts.repeat = Some(repeat);
+    Ok(())
+}
+
+// ---------- 相对时间 ----------
+
+regex!(
+    RE_OFFSET_EN,
+    r"\b(?:in|after)\s+(\d+(?:\.\d+)?|an?|half\s+an?)\s*(seconds?|secs?|minutes?|mins?|hours?|hrs?|days?|weeks?)(\s+and\s+a\s+half)?\b|\b(\d+(?:\.\d+)?|an?)\s*(seconds?|secs?|minutes?|mins?|hours?|hrs?|days?|weeks?)\s+(?:from\s+now|later)\b"
+);
+regex!(
+    RE_OFFSET_ZH,
+    r"过?(\d+)?个?(半)?个?(秒钟?|分钟|小时|钟头|天|周|星期|礼拜)(?:以后|之后|后)|过(\d+)个?(分钟|小时|钟头)"
+);
+
+fn take_offset(scan: &mut Scanner, parts: &mut Parts) -> anyhow::Result<()> {
+    let (amount, unit) = if let Some(caps) = scan.take(&RE_OFFSET_EN) {
+        let (amount, unit, half) = if caps[1].is_empty() {
+            (caps[4].as_str(), caps[5].as_str(), false)
+        } else {
+            (caps[1].as_str(), caps[2].as_str(), !caps[3].is_empty())
+        };
+        let amount = match amount {
+            "a" | "an" => 1.0,
+            _ if amount.starts_with("half") => 0.5,
+            _ => amount.parse().unwrap_or(1.0),
+        };
+        (amount + if half { 0.5 } else { 0.0 }, unit.to_string())
+    } else if let Some(caps) = scan.take_if(&RE_OFFSET_ZH, |caps| {
+        // 只有单位没有数量（如 "天后"）时不算相对时间，保留文本
+        !(caps[1].is_empty() && caps[2].is_empty() && caps[4].is_empty())
+    }) {
+        if caps[4].is_empty() {
+            let amount = caps[1].parse().unwrap_or(0.0);
+            let half = if caps[2].is_empty() { 0.0 } else { 0.5 };
+            (amount + half, caps[3].clone())
+        } else {
+            (caps[4].parse().unwrap_or(1.0), caps[5].clone())
+        }
+    } else {
+        return Ok(());
+    };
+
+    let unit_secs = match unit.chars().next() {
+        Some('s' | '秒') => 1.0,
+        Some('m' | '分') => 60.0,
+        Some('h' | '小' | '钟') => 3600.0,
+        Some('d' | '天') => 86400.0,
+        _ => 604800.0,
+    };
+    // 超出 i64 的秒数在转换时饱和，再由 try_seconds 拒绝
+    let offset = Duration::try_seconds((amount * unit_secs) as i64)
+        .ok_or_else(|| anyhow::anyhow!("Relative offset is too large"))?;
+    parts.offset = Some(offset);
+    Ok(())
+}
+
+// ---------- 日期 ----------
+
+regex!(RE_ISO_DATE, r"(\d{4})[-/.](\d{1,2})[-/.](\d{1,2})");
+regex!(RE_ZH_DATE, r"(?:(\d{4})年)?(\d{1,2})月(\d{1,2})[日号]?");
+regex!(
+    RE_EN_MONTH_DAY,
+    r"\b(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+(\d{1,2})(?:st|nd|rd|th)?(?:,?\s+(\d{4}))?\b"
+);
+regex!(
+    RE_EN_DAY_MONTH,
+    r"\b(\d{1,2})(?:st|nd|rd|th)?\s+(?:of\s+)?(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?(?:,?\s+(\d{4}))?\b"
+);
+regex!(RE_SLASH_DATE, r"(\d{1,2})/(\d{1,2})\b");
+regex!(RE_ZH_WEEKDAY, r"(下下|下个?|这个?|本)?(?:周|星期|礼拜)([1-6日天])");
+static RE_EN_WEEKDAY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"\b(?:(next|this|coming)\s+)?({})\b", EN_WEEKDAY))
+        .expect("invalid built-in regex")
+});
+regex!(RE_ZH_DAY_OF_MONTH, r"(\d{1,2})[号日]");
+regex!(
+    RE_RELATIVE_DAY,
+    r"\b(?:the\s+)?day\s+after\s+tomorrow\b|\btomorrow\b|\btoday\b|\btonight\b|大后天|后天|明天|明日|明早|明晚|今天|今日|今早|今晚|今夜"
+);
+
+fn take_date(scan: &mut Scanner, parts: &mut Parts, today: NaiveDate) {
+    let ymd = |y: &str, m: &str, d: &str| -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y.parse().ok()?, m.parse().ok()?, d.parse().ok()?)
+    };
+    // 未写年份时取今年，已过去则顺延到明年
+    let month_day = |parts: &mut Parts, year: &str, month: Option<u32>, day: &str| {
+        let day: u32 = day.parse().unwrap_or(0);
+        let Some(month) = month else { return };
+        if year.is_empty() {
+            parts.date = NaiveDate::from_ymd_opt(today.year(), month, day);
+            parts.roll = Some(Roll::Year);
+        } else {
+            parts.date = year
+                .parse()
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, month, day));
+        }
+    };
+
+    if let Some(caps) = scan.take(&RE_ISO_DATE) {
+        parts.date = ymd(&caps[1], &caps[2], &caps[3]);
+    } else if let Some(caps) = scan.take(&RE_ZH_DATE) {
+        month_day(parts, &caps[1], caps[2].parse().ok(), &caps[3]);
+    } else if let Some(caps) = scan.take(&RE_EN_MONTH_DAY) {
+        month_day(parts, &caps[3], month_from(&caps[1]), &caps[2]);
+    } else if let Some(caps) = scan.take(&RE_EN_DAY_MONTH) {
+        month_day(parts, &caps[3], month_from(&caps[2]), &caps[1]);
+    } else if let Some(caps) = scan.take(&RE_SLASH_DATE) {
+        let (first, second): (u32, u32) =
+            (caps[1].parse().unwrap_or(0), caps[2].parse().unwrap_or(0));
+        month_day(parts, "", Some(first), &caps[2]);
+        if first != second && first <= 12 && second <= 12 {
+            parts.ambiguous = true;
+            parts.notes.push(format!(
+                "'{}/{}' read as month/day; it may also mean day/month",
+                first, second
+            ));
+        }
+    } else if let Some(caps) = scan.take(&RE_ZH_WEEKDAY) {
+        let Some(weekday) = weekday_from(&caps[2]) else { return };
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let this_week = monday + Duration::days(weekday.num_days_from_monday() as i64);
+        parts.date = Some(match caps[1].as_str() {
+            "下下" => this_week + Duration::days(14),
+            "下" | "下个" => this_week + Duration::days(7),
+            "这" | "这个" | "本" => this_week,
+            _ => {
+                parts.roll = Some(Roll::Week);
+                next_weekday(today, weekday, true)
+            }
+        });
+    } else if let Some(caps) = scan.take(&RE_EN_WEEKDAY) {
+        let Some(weekday) = weekday_from(&caps[2]) else { return };
+        let date = match caps[1].as_str() {
+            "next" => {
+                let date = next_weekday(today, weekday, false);
+                // 本周内还没到的星期几，"next" 也可能指下一周的那天
+                if weekday.num_days_from_monday() > today.weekday().num_days_from_monday() {
+                    parts.ambiguous = true;
+                    parts.notes.push(format!(
+                        "'next {}' read as {}; it may also mean {}",
+                        weekday,
+                        date,
+                        date + Duration::days(7)
+                    ));
+                }
+                date
+            }
+            _ => {
+                parts.roll = Some(Roll::Week);
+                next_weekday(today, weekday, true)
+            }
+        };
+        parts.date = Some(date);
+    } else if let Some(caps) = scan.take(&RE_ZH_DAY_OF_MONTH) {
+        let day: u32 = caps[1].parse().unwrap_or(0);
+        parts.date = NaiveDate::from_ymd_opt(today.year(), today.month(), day);
+        parts.roll = Some(Roll::Month);
+    }
+
+    // 相对日期可与上面的星期几同时出现时以相对日期为准（如 "tomorrow"）
+    if let Some(caps) = scan.take(&RE_RELATIVE_DAY) {
+        let word = caps[0].trim();
+        let (days, period) = match word {
+            "大后天" => (3, None),
+            "后天" => (2, None),
+            "明天" | "明日" | "tomorrow" => (1, None),
+            "明早" => (1, Some(Period::Morning)),
+            "明晚" => (1, Some(Period::Night)),
+            "今早" => (0, Some(Period::Morning)),
+            "今晚" | "今夜" | "tonight" => (0, Some(Period::Night)),
+            "今天" | "今日" | "today" => (0, None),
+            _ => (2, None),
+        };
+        parts.date = Some(today + Duration::days(days));
+        parts.roll = None;
+        if period.is_some() {
+            parts.period = period;
+        }
+    }
+}
+
+/// 下一个指定的星期几；include_today 为 true 时可以是今天
+fn next_weekday(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let diff = (weekday.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+    let diff = if diff == 0 && !include_today { 7 } else { diff };
+    today + Duration::days(diff)
+}
+
+// ---------- 时段与时刻 ----------
+
+regex!(
+    RE_PERIOD,
+    r"凌晨|清晨|早上|早晨|上午|中午|下午|傍晚|晚上|夜里|夜间|半夜|\b(?:in\s+the\s+|this\s+)?(?:morning|afternoon|evening|night)\b"
+);
+regex!(RE_NOON, r"\b(?:at\s+)?(noon|midday|midnight)\b");
+regex!(
+    RE_MERIDIEM,
+    r"(?:\bat\s+)?(\d{1,2})(?::(\d{2}))?\s*(am|pm|a\.m\.|p\.m\.)"
+);
+regex!(RE_COLON_TIME, r"(?:\bat\s+)?(\d{1,2}):(\d{2})(?::\d{2})?");
+regex!(RE_ZH_TIME, r"(\d{1,2})[点时](?:(\d{1,2})分?|(半)|([13])刻|整)?");
+regex!(RE_BARE_HOUR, r"\bat\s+(\d{1,2})(?:\s*o'?clock)?\b|\b(\d{1,2})\s*o'?clock\b");
+
+fn take_period(scan: &mut Scanner, parts: &mut Parts) {
+    let Some(caps) = scan.take(&RE_PERIOD) else { return };
+    let word = caps[0].trim();
+    parts.period = Some(match word {
+        "凌晨" => Period::EarlyMorning,
+        "清晨" | "早上" | "早晨" | "上午" => Period::Morning,
+        "中午" => Period::Noon,
+        "下午" => Period::Afternoon,
+        "傍晚" => Period::Evening,
+        "晚上" | "夜里" | "夜间" | "半夜" => Period::Night,
+        _ if word.ends_with("morning") => Period::Morning,
+        _ if word.ends_with("afternoon") => Period::Afternoon,
+        _ if word.ends_with("evening") => Period::Evening,
+        _ => Period::Night,
+    });
+}
+
+fn take_time(scan: &mut Scanner, parts: &mut Parts) {
+    let number = |s: &str| s.parse::<u32>().unwrap_or(0);
+    let clock = if let Some(caps) = scan.take(&RE_NOON) {
+        let midnight = &caps[1] == "midnight";
+        Clock {
+            hour: if midnight { 0 } else { 12 },
+            minute: 0,
+            exact: true,
+            midnight,
+        }
+    } else if let Some(caps) = scan.take(&RE_MERIDIEM) {
+        let hour = number(&caps[1]) % 12;
+        let pm = caps[3].starts_with('p');
+        Clock {
+            hour: if pm { hour + 12 } else { hour },
+            minute: number(&caps[2]),
+            exact: true,
+            midnight: false,
+        }
+    } else if let Some(caps) = scan.take(&RE_COLON_TIME) {
+        // 冒号写法按 24 小时制理解，但 "下午3:30" 这类仍由时段决定
+        Clock {
+            hour: number(&caps[1]),
+            minute: number(&caps[2]),
+            exact: parts.period.is_none(),
+            midnight: false,
+        }
+    } else if let Some(caps) = scan.take(&RE_ZH_TIME) {
+        let minute = if !caps[3].is_empty() {
+            30
+        } else if !caps[4].is_empty() {
+            number(&caps[4]) * 15
+        } else {
+            number(&caps[2])
+        };
+        let hour = number(&caps[1]);
+        Clock {
+            hour,
+            minute,
+            exact: hour == 0 || hour > 12,
+            midnight: false,
+        }
+    } else if let Some(caps) = scan.take(&RE_BARE_HOUR) {
+        let hour = number(if caps[1].is_empty() { &caps[2] } else { &caps[1] });
+        Clock {
+            hour,
+            minute: 0,
+            exact: hour == 0 || hour > 12,
+            midnight: false,
+        }
+    } else {
+        return;
+    };
+    parts.time = Some(clock);
+}
+
+// ---------- 组合 ----------
+
+/// 按时段换算成 24 小时制，返回 (小时, 需要顺延的天数)；无法确定上下午时返回 None
+fn apply_period(clock: &Clock, period: Option<Period>) -> Option<(u32, i64)> {
+    let hour = clock.hour;
+    if clock.exact {
+        return Some((hour, 0));
+    }
+    let resolved = match period? {
+        Period::EarlyMorning | Period::Morning => (hour % 12, 0),
+        Period::Noon if hour <= 2 => (hour + 12, 0),
+        Period::Noon => (hour, 0),
+        Period::Afternoon | Period::Evening => (hour % 12 + 12, 0),
+        // "晚上12点" 是次日 0 点，"夜里2点" 是次日凌晨
+        Period::Night if hour == 12 => (0, 1),
+        Period::Night if hour <= 4 => (hour, 1),
+        Period::Night => (hour % 12 + 12, 0),
+    };
+    Some(resolved)
+}
+
+/// 没有上下午信息时的常见习惯：1-6 点按下午，7-11 点按上午
+fn guess_hour(hour: u32) -> u32 {
+    if (1..=6).contains(&hour) { hour + 12 } else { hour }
+}
+
+fn default_clock(period: Option<Period>) -> (u32, u32) {
+    match period {
+        Some(Period::EarlyMorning) => (6, 0),
+        Some(Period::Noon) => (12, 0),
+        Some(Period::Afternoon) => (15, 0),
+        Some(Period::Evening) => (18, 0),
+        Some(Period::Night) => (20, 0),
+        _ => (9, 0),
+    }
+}
+
+/// 确定时刻：返回 (时, 分, 顺延天数)
+fn resolve_clock(parts: &mut Parts, explicit_date: bool) -> (u32, u32, i64) {
+    let period = parts.period;
+    let Some(clock) = &parts.time else {
+        let (hour, minute) = default_clock(period);
+        parts.defaulted = true;
+        parts.notes.push(format!(
+            "No time of day given, assumed {:02}:{:02}",
+            hour, minute
+        ));
+        return (hour, minute, 0);
+    };
+    if clock.midnight && explicit_date {
+        parts
+            .notes
+            .push("'midnight' read as the end of the given day".to_string());
+        return (0, 0, 1);
+    }
+    match apply_period(clock, period) {
+        Some((hour, shift)) => (hour, clock.minute, shift),
+        None => {
+            let hour = guess_hour(clock.hour);
+            parts.ambiguous = true;
+            parts.notes.push(format!(
+                "AM/PM not specified for {}:{:02}, assumed {:02}:{:02}",
+                clock.hour, clock.minute, hour, clock.minute
+            ));
+            (hour, clock.minute, 0)
+        }
+    }
+}
+
+fn to_utc(tz: Tz, date: NaiveDate, hour: u32, minute: u32) -> anyhow::Result<DateTime<Utc>> {
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)
+        .ok_or_else(|| anyhow::anyhow!("Invalid time {}:{:02}", hour, minute))?;
+    tz.from_local_datetime(&NaiveDateTime::new(date, time))
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} {:02}:{:02} does not exist in timezone {}",
+                date,
+                hour,
+                minute,
+                tz.name()
+            )
+        })
+}
+
+fn roll_forward(date: NaiveDate, roll: Roll) -> Option<NaiveDate> {
+    match roll {
+        Roll::Day => Some(date + Duration::days(1)),
+        Roll::Week => Some(date + Duration::days(7)),
+        Roll::Month => date.checked_add_months(Months::new(1)),
+        Roll::Year => date.checked_add_months(Months::new(12)),
+    }
+}
+
+fn resolve_once(
+    parts: &mut Parts,
+    reference: DateTime<Utc>,
+    today: NaiveDate,
+    tz: Tz,
+) -> anyhow::Result<ParsedTime> {
+    let at = if let Some(offset) = parts.offset {
+        if parts.date.is_some() || parts.time.is_some() {
+            parts.ambiguous = true;
+            parts
+                .notes
+                .push("Both a relative offset and a date/time were given; used the offset".to_string());
+        }
+        reference
+            .checked_add_signed(offset)
+            .ok_or_else(|| anyhow::anyhow!("Offset is out of range"))?
+    } else {
+        let explicit_date = parts.date.is_some();
+        let mut date = parts.date.unwrap_or(today);
+        let mut roll = parts.roll;
+        if !explicit_date {
+            roll = Some(Roll::Day);
+        }
+
+        // 只给出时刻且不确定上下午时，取最近的一个未来时间
+        let unsure = parts
+            .time
+            .as_ref()
+            .is_some_and(|clock| apply_period(clock, parts.period).is_none());
+        let upcoming = parts.time.as_ref().filter(|_| unsure && !explicit_date).and_then(|clock| {
+            let (hour, minute) = (clock.hour % 12, clock.minute);
+            [hour, hour + 12]
+                .into_iter()
+                .flat_map(|hour| [today, today + Duration::days(1)].map(|day| (day, hour)))
+                .filter_map(|(day, hour)| {
+                    let at = to_utc(tz, day, hour, minute).ok()?;
+                    (at > reference).then_some((at, day, hour, minute))
+                })
+                .min_by_key(|(at, ..)| *at)
+        });
+        let (hour, minute, shift) = match upcoming {
+            Some((_, day, hour, minute)) => {
+                date = day;
+                roll = None;
+                parts.ambiguous = true;
+                parts.notes.push(format!(
+                    "AM/PM not specified, picked the next upcoming {:02}:{:02}",
+                    hour, minute
+                ));
+                (hour, minute, 0)
+            }
+            None => resolve_clock(parts, explicit_date),
+        };
+
+        date += Duration::days(shift);
+        let mut at = to_utc(tz, date, hour, minute)?;
+        if at <= reference
+            && let Some(roll) = roll
+            && let Some(next) = roll_forward(date, roll)
+        {
+            at = to_utc(tz, next, hour, minute)?;
+            parts
+                .notes
+                .push(format!("{} has already passed, moved to {}", date, next));
+        }
+        at
+    };
+
+    Ok(ParsedTime {
+        kind: JobKind::Once,
+        at,
+        local: at.with_timezone(&tz).to_rfc3339(),
+        timezone: tz.name().to_string(),
+        schedule: None,
+        confidence: Confidence::High,
+        ambiguous: false,
+        notes: Vec::new(),
+    })
+}
+
+fn resolve_repeat(
+    repeat: Repeat,
+    parts: &mut Parts,
+    reference: DateTime<Utc>,
+    today: NaiveDate,
+    tz: Tz,
+) -> anyhow::Result<ParsedTime> {
+    // 天、周为单位且给出时刻时按日历规则处理，如 "每隔2天早上8点"
+    let repeat = match repeat {
+        Repeat::Every(n, 'd') if parts.time.is_some() => Repeat::Daily(n),
+        Repeat::Every(n, 'w') if parts.time.is_some() => Repeat::Weekly(n, vec![today.weekday()]),
+        other => other,
+    };
+
+    let rule = match &repeat {
+        Repeat::Every(n, unit) => return interval_schedule(*n, *unit, reference, tz),
+        Repeat::Daily(1) => "FREQ=DAILY".to_string(),
+        Repeat::Daily(n) => format!("FREQ=DAILY;INTERVAL={}", n),
+        Repeat::Weekly(n, days) => {
+            const CODES: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+            let days: Vec<&str> = days
+                .iter()
+                .map(|day| CODES[day.num_days_from_monday() as usize])
+                .collect();
+            let interval = if *n > 1 {
+                format!(";INTERVAL={}", n)
+            } else {
+                String::new()
+            };
+            format!("FREQ=WEEKLY{};BYDAY={}", interval, days.join(","))
+        }
+        Repeat::Monthly(day) => format!("FREQ=MONTHLY;BYMONTHDAY={}", day),
+    };
+    let (hour, minute, _) = resolve_clock(parts, true);
+    let dtstart = if tz == Tz::UTC {
+        format!("DTSTART:{}T000000Z", today.format("%Y%m%d"))
+    } else {
+        format!("DTSTART;TZID={}:{}T000000", tz.name(), today.format("%Y%m%d"))
+    };
+    let text = format!(
+        "{}\nRRULE:{};BYHOUR={};BYMINUTE={};BYSECOND=0",
+        dtstart, rule, hour, minute
+    );
+    let (set, text) = parse_rrule(&text, reference)?;
+    let at = rrule_next_after(&set, reference)
+        .ok_or_else(|| anyhow::anyhow!("Recurrence has no upcoming occurrence"))?;
+
+    Ok(ParsedTime {
+        kind: JobKind::Rrule,
+        at,
+        local: at.with_timezone(&tz).to_rfc3339(),
+        timezone: tz.name().to_string(),
+        schedule: Some(ScheduleSpec {
+            rrule: Some(text),
+            ..Default::default()
+        }),
+        confidence: Confidence::High,
+        ambiguous: false,
+        notes: Vec::new(),
+    })
+}
+
+fn interval_schedule(
+    amount: u32,
+    unit: char,
+    reference: DateTime<Utc>,
+    tz: Tz,
+) -> anyhow::Result<ParsedTime> {
+    let every = format!("{}{}", amount, unit);
+    let interval = crate::duration::parse_duration(&every)?;
+    let at = Duration::from_std(interval)
+        .ok()
+        .and_then(|interval| reference.checked_add_signed(interval))
+        .ok_or_else(|| anyhow::anyhow!("Interval '{}' is too large", every))?;
+    Ok(ParsedTime {
+        kind: JobKind::Interval,
+        at,
+        local: at.with_timezone(&tz).to_rfc3339(),
+        timezone: tz.name().to_string(),
+        schedule: Some(ScheduleSpec {
+            every: Some(every),
+            ..Default::default()
+        }),
+        confidence: Confidence::High,
+        ambiguous: false,
+        notes: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference() -> DateTime<Utc> {
+        // 2026-10-19 是星期一
+        Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap()
+    }
+
+    fn parse_utc(text: &str) -> anyhow::Result<ParsedTime> {
+        parse(text, reference(), Tz::UTC)
+    }
+
+    #[test]
+    fn parses_rfc3339() {
+        let parsed = parse_utc("2026-10-20T08:00:00+08:00").unwrap();
+        assert_eq!(parsed.kind, JobKind::Once);
+        assert_eq!(parsed.at, Utc.with_ymd_and_hms(2026, 10, 20, 0, 0, 0).unwrap());
+        assert_eq!(parsed.confidence, Confidence::High);
+    }
+
+    #[test]
+    fn parses_relative_offsets() {
+        let parsed = parse_utc("in 2 hours").unwrap();
+        assert_eq!(parsed.at, reference() + Duration::hours(2));
+        let parsed = parse_utc("3天后").unwrap();
+        assert_eq!(parsed.at, reference() + Duration::days(3));
+        let parsed = parse_utc("半小时后").unwrap();
+        assert_eq!(parsed.at, reference() + Duration::minutes(30));
+    }
+
+    #[test]
+    fn parses_date_and_time_in_timezone() {
+        let parsed = parse("明天早上九点", reference(), Tz::Asia__Shanghai).unwrap();
+        assert_eq!(parsed.kind, JobKind::Once);
+        assert_eq!(parsed.at, Utc.with_ymd_and_hms(2026, 10, 20, 1, 0, 0).unwrap());
+        assert!(!parsed.ambiguous);
+    }
+
+    #[test]
+    fn parses_recurring_rules() {
+        let parsed = parse_utc("every 2 hours").unwrap();
+        assert_eq!(parsed.kind, JobKind::Interval);
+        assert_eq!(parsed.schedule.unwrap().every.as_deref(), Some("2h"));
+
+        let parsed = parse_utc("每个工作日 9:30").unwrap();
+        assert_eq!(parsed.kind, JobKind::Rrule);
+        assert_eq!(parsed.at, Utc.with_ymd_and_hms(2026, 10, 19, 9, 30, 0).unwrap());
+        let rrule = parsed.schedule.unwrap().rrule.unwrap();
+        assert!(rrule.contains("BYDAY=MO,TU,WE,TH,FR"), "{}", rrule);
+    }
+
+    #[test]
+    fn rejects_oversized_amounts() {
+        for text in [
+            "in 99999999999999999999 weeks",
+            "in 1000000000000 weeks",
+            "every 4000000000 weeks",
+            "every 99999999999 hours",
+            "每隔99999999999天",
+        ] {
+            let err = parse_utc(text).unwrap_err();
+            assert!(err.to_string().contains("too large"), "{}: {}", text, err);
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_calendar_intervals() {
+        for text in ["every 4000000000 days at 9am", "every 4000000000 weeks at 9am"] {
+            let err = parse_utc(text).unwrap_err();
+            assert!(err.to_string().contains("INTERVAL"), "{}: {}", text, err);
+        }
+    }
+
+    #[test]
+    fn unit_without_amount_is_not_an_offset() {
+        let mut scan = Scanner::new("天后");
+        let mut parts = Parts::default();
+        take_offset(&mut scan, &mut parts).unwrap();
+        assert!(parts.offset.is_none());
+        assert_eq!(scan.text, "天后");
+    }
+}
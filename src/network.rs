@@ -0,0 +1,224 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ipnet::IpNet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::debug;
+
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// 网段，配置中可写 CIDR（10.0.0.0/8）或单个 IP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Network(IpNet);
+
+impl Network {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 映射的 IPv6 地址（::ffff:a.b.c.d）按 IPv4 处理
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            IpAddr::V4(_) => ip,
+        };
+        self.0.contains(&ip)
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        let value = value.trim();
+        if let Ok(net) = value.parse::<IpNet>() {
+            return Ok(Network(net.trunc()));
+        }
+        value
+            .parse::<IpAddr>()
+            .map(|ip| Network(IpNet::from(ip)))
+            .map_err(|_| anyhow::anyhow!("Invalid network {}: use a CIDR like 10.0.0.0/8 or an IP", value))
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for Network {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Network {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+pub fn contains(networks: &[Network], ip: IpAddr) -> bool {
+    networks.iter().any(|network| network.contains(ip))
+}
+
+/// 解析后的客户端 IP，由 network_middleware 放入请求扩展
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+/// 访问来源控制：允许访问的网段，以及可信任 X-Forwarded-For 的反向代理
+pub struct NetworkPolicy {
+    // 为空表示不限制
+    allowed: Vec<Network>,
+    trusted_proxies: Vec<Network>,
+}
+
+impl NetworkPolicy {
+    pub fn new(allowed: Vec<Network>, trusted_proxies: Vec<Network>) -> Self {
+        Self {
+            allowed,
+            trusted_proxies,
+        }
+    }
+
+    /// 连接来自可信代理时，从右向左跳过 X-Forwarded-For 中的可信代理，取第一个其他地址
+    fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !contains(&self.trusted_proxies, peer) {
+            return peer;
+        }
+        let hops: Vec<&str> = headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+
+        let mut client = peer;
+        for hop in hops.iter().rev() {
+            // 无法解析的地址之后的内容不可信
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !contains(&self.trusted_proxies, ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// 解析客户端 IP 并检查 allowed_networks，需在 auth_middleware 之前执行
+pub async fn network_middleware(
+    State(policy): State<Arc<NetworkPolicy>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(peer) = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+    else {
+        return next.run(request).await;
+    };
+
+    let ip = policy.client_ip(peer, request.headers());
+    if !policy.allowed.is_empty() && !contains(&policy.allowed, ip) {
+        debug!("Rejected request from {} (peer {})", ip, peer);
+        return (StatusCode::FORBIDDEN, "Forbidden: address not allowed").into_response();
+    }
+    request.extensions_mut().insert(ClientIp(ip));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn nets(values: &[&str]) -> Vec<Network> {
+        values.iter().map(|value| value.parse().unwrap()).collect()
+    }
+
+    fn forwarded(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(FORWARDED_FOR_HEADER, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn policy() -> NetworkPolicy {
+        NetworkPolicy::new(Vec::new(), nets(&["10.0.0.0/8"]))
+    }
+
+    #[test]
+    fn parses_cidrs_and_single_ips() {
+        assert_eq!("10.1.2.3/8".parse::<Network>().unwrap().to_string(), "10.0.0.0/8");
+        assert_eq!(" 192.168.1.1 ".parse::<Network>().unwrap().to_string(), "192.168.1.1/32");
+        assert_eq!("::1".parse::<Network>().unwrap().to_string(), "::1/128");
+        assert_eq!("fd00::/8".parse::<Network>().unwrap().to_string(), "fd00::/8");
+    }
+
+    #[test]
+    fn rejects_invalid_cidrs() {
+        for value in ["", "abc", "10.0.0", "10.0.0.0/33", "10.0.0.0/", "::1/129", "10.0.0.0/8/8"] {
+            assert!(value.parse::<Network>().is_err(), "{}", value);
+        }
+        assert!(serde_json::from_str::<Network>("\"300.0.0.0/8\"").is_err());
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_match_ipv4_networks() {
+        let loopback = nets(&["127.0.0.0/8"]);
+        assert!(contains(&loopback, ip("::ffff:127.0.0.1")));
+        assert!(!contains(&loopback, ip("::ffff:10.0.0.1")));
+        assert!(!contains(&loopback, ip("::1")));
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peers() {
+        let headers = forwarded(&["203.0.113.5"]);
+        assert_eq!(policy().client_ip(ip("198.51.100.7"), &headers), ip("198.51.100.7"));
+    }
+
+    #[test]
+    fn walks_a_trusted_proxy_chain() {
+        let headers = forwarded(&["203.0.113.5, 10.0.0.2"]);
+        assert_eq!(policy().client_ip(ip("10.0.0.1"), &headers), ip("203.0.113.5"));
+        // 多个请求头按顺序拼接
+        let headers = forwarded(&["203.0.113.5", "10.0.0.3, 10.0.0.2"]);
+        assert_eq!(policy().client_ip(ip("10.0.0.1"), &headers), ip("203.0.113.5"));
+        // 没有请求头时为连接地址
+        assert_eq!(policy().client_ip(ip("10.0.0.1"), &HeaderMap::new()), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn ignores_spoofed_left_most_entries() {
+        let headers = forwarded(&["127.0.0.1, 1.2.3.4, 203.0.113.5"]);
+        assert_eq!(policy().client_ip(ip("10.0.0.1"), &headers), ip("203.0.113.5"));
+    }
+
+    #[test]
+    fn stops_at_unparseable_hops() {
+        let headers = forwarded(&["203.0.113.5, unknown"]);
+        assert_eq!(policy().client_ip(ip("10.0.0.1"), &headers), ip("10.0.0.1"));
+        let headers = forwarded(&["unknown, 203.0.113.5, 10.0.0.2"]);
+        assert_eq!(policy().client_ip(ip("10.0.0.1"), &headers), ip("203.0.113.5"));
+    }
+
+    #[test]
+    fn trusts_ipv4_mapped_proxy_peers() {
+        let headers = forwarded(&["203.0.113.5"]);
+        assert_eq!(policy().client_ip(ip("::ffff:10.0.0.1"), &headers), ip("203.0.113.5"));
+    }
+}
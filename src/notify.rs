@@ -1,10 +1,18 @@
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{error, info};
 
+use crate::metrics::{BARK_RESPONSES, METRICS, NOTIFICATIONS_FAILED, NOTIFICATIONS_SENT};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotifyRequest {
+    #[serde(default)]
     pub title: String,
+    #[serde(default)]
     pub body: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sound: Option<String>,
@@ -22,6 +30,12 @@ pub struct NotifyRequest {
     pub auto_copy: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub badge: Option<i32>,
+    // 引用的模板名称，创建任务/发送前由 TemplateStore 解析并清空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    // 目标设备名称，不填时发送到 default 设备；发送给 Bark 前移除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,42 +46,116 @@ pub struct BarkResponse {
     pub timestamp: Option<i64>,
 }
 
+/// 请求未指定 device 时使用的设备名称（对应配置项 device_key）
+pub const DEFAULT_DEVICE: &str = "default";
+
 #[derive(Debug, Clone)]
 pub struct Notifier {
     client: Client,
     pub base_url: String,
-    pub device_key: String,
+    // 设备名称 -> 设备密钥
+    pub devices: BTreeMap<String, String>,
+    // 最近一次推送成功的时间，供 /ready 使用
+    last_success: Arc<Mutex<Option<DateTime<Utc>>>>,
 }
 
 impl Notifier {
-    pub fn new(base_url: String, device_key: String) -> Self {
+    pub fn new(base_url: String, devices: BTreeMap<String, String>) -> Self {
         Self {
             client: Client::new(),
             base_url,
-            device_key,
+            devices,
+            last_success: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 检查设备名称是否已配置，device 为空表示默认设备
+    pub fn check_device(&self, device: Option<&str>) -> anyhow::Result<()> {
+        let device = device.unwrap_or(DEFAULT_DEVICE);
+        if self.devices.contains_key(device) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Unknown device: {}", device))
         }
     }
 
+    pub fn last_success(&self) -> Option<DateTime<Utc>> {
+        *self.last_success.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 探测 Bark 服务是否可达（GET {bark_url}/ping）
+    pub async fn ping(&self, timeout: Duration) -> anyhow::Result<()> {
+        let url = format!("{}/ping", self.base_url);
+        let response = self.client.get(&url).timeout(timeout).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Bark ping returned HTTP {}", status));
+        }
+        let bark_resp: BarkResponse = response.json().await?;
+        if bark_resp.code != 200 {
+            return Err(anyhow::anyhow!("Bark ping error: {}", bark_resp.message));
+        }
+        Ok(())
+    }
+
+    /// 立即推送（/notify）
     pub async fn send(&self, req: &NotifyRequest) -> anyhow::Result<BarkResponse> {
-        let url = format!("{}/{}", self.base_url, self.device_key);
+        self.send_via(req, "notify").await
+    }
+
+    /// 推送并按 channel（notify 或任务类型）记录指标
+    pub async fn send_via(&self, req: &NotifyRequest, channel: &str) -> anyhow::Result<BarkResponse> {
+        let device = req.device.as_deref().unwrap_or(DEFAULT_DEVICE);
+        let result = self.post(req, device).await;
+        let labels = [("channel", channel), ("device", device)];
+        match result {
+            Ok(_) => {
+                METRICS.inc(NOTIFICATIONS_SENT, &labels);
+                *self.last_success.lock().unwrap_or_else(|e| e.into_inner()) = Some(Utc::now());
+            }
+            Err(_) => METRICS.inc(NOTIFICATIONS_FAILED, &labels),
+        }
+        result
+    }
+
+    async fn post(&self, req: &NotifyRequest, device: &str) -> anyhow::Result<BarkResponse> {
+        let Some(device_key) = self.devices.get(device) else {
+            return Err(anyhow::anyhow!("Unknown device: {}", device));
+        };
+        let url = format!("{}/{}", self.base_url, device_key);
         
-        info!("Sending notification to {}: title={}", url, req.title);
+        info!("Sending notification to device {}: title={}", device, req.title);
         
+        let mut payload = req.clone();
+        payload.device = None;
+        let started = Instant::now();
         let response = self.client
             .post(&url)
-            .json(req)
+            .json(&payload)
             .send()
-            .await?;
+            .await;
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                METRICS.observe_latency(started.elapsed().as_secs_f64());
+                METRICS.inc(BARK_RESPONSES, &[("code", "error")]);
+                return Err(e.into());
+            }
+        };
         
         let status = response.status();
-        let body = response.text().await?;
+        let body = response.text().await;
+        METRICS.observe_latency(started.elapsed().as_secs_f64());
+        let body = body?;
         
         if !status.is_success() {
+            METRICS.inc(BARK_RESPONSES, &[("code", status.as_str())]);
             error!("Bark API error: status={}, body={}", status, body);
             return Err(anyhow::anyhow!("Bark API error: {}", body));
         }
         
         let bark_resp: BarkResponse = serde_json::from_str(&body)?;
+        METRICS.inc(BARK_RESPONSES, &[("code", &bark_resp.code.to_string())]);
         
         if bark_resp.code != 200 {
             error!("Bark returned error: {:?}", bark_resp);
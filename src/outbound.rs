@@ -0,0 +1,315 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder, RequestBuilder, Response, Url};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use crate::network::{self, Network};
+
+/// 读取外部响应的最大字节数
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+/// 外部请求允许设置的最长超时秒数
+pub const MAX_TIMEOUT_SECS: u64 = 60;
+// 最多跟随的重定向次数，与 reqwest 默认一致
+const MAX_REDIRECTS: usize = 10;
+
+/// 任务访问外部地址的限制：默认拒绝回环、私有、链路本地等内网地址，allowed 中的网段除外
+pub struct OutboundPolicy {
+    allowed: Vec<Network>,
+}
+
+impl OutboundPolicy {
+    pub fn new(allowed: Vec<Network>) -> Self {
+        Self { allowed }
+    }
+
+    fn permits(&self, ip: IpAddr) -> bool {
+        !is_internal(ip) || network::contains(&self.allowed, ip)
+    }
+
+    fn check_ip(&self, host: &str, ip: IpAddr) -> anyhow::Result<()> {
+        if self.permits(ip) {
+            return Ok(());
+        }
+        if host == ip.to_string() {
+            return Err(anyhow::anyhow!(
+                "internal address {} is not allowed, add it to outbound_allowed_networks to allow",
+                ip
+            ));
+        }
+        Err(anyhow::anyhow!(
+            "{} resolves to internal address {}, add it to outbound_allowed_networks to allow",
+            host,
+            ip
+        ))
+    }
+
+    /// 地址中直接写 IP 时检查该 IP；域名在连接时由解析器检查
+    fn check_literal(&self, url: &Url) -> anyhow::Result<()> {
+        match literal_ip(url) {
+            Some(ip) => self.check_ip(&ip.to_string(), ip),
+            None => Ok(()),
+        }
+    }
+
+    /// 创建任务时解析地址中的主机，任一地址不被允许则拒绝
+    pub async fn check_url(&self, url: &str) -> anyhow::Result<()> {
+        let parsed = Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid url '{}': {}", url, e))?;
+        self.check_literal(&parsed)?;
+        let Some(domain) = parsed.host_str().filter(|_| literal_ip(&parsed).is_none()) else {
+            return Ok(());
+        };
+        let port = parsed.port_or_known_default().unwrap_or(80);
+        let addrs = tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to resolve {}: {}", domain, e))?;
+        for addr in addrs {
+            self.check_ip(domain, addr.ip())?;
+        }
+        Ok(())
+    }
+}
+
+fn literal_ip(url: &Url) -> Option<IpAddr> {
+    let host = url.host_str()?;
+    host.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+/// 回环、私有、链路本地、运营商级 NAT、未指定和广播地址，IPv4 映射的 IPv6 地址按 IPv4 判断
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_internal_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_internal_v4(v4),
+            None => is_internal_v6(v6),
+        },
+    }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        // 0.0.0.0/8 和 100.64.0.0/10
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+}
+
+fn is_internal_v6(ip: Ipv6Addr) -> bool {
+    ip.is_loopback() || ip.is_unspecified() || ip.is_unique_local() || ip.is_unicast_link_local()
+}
+
+/// 解析域名后检查全部地址，防止通过 DNS 指向内网
+struct GuardedResolver(Arc<OutboundPolicy>);
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = Arc::clone(&self.0);
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0)).await?.collect();
+            for addr in &addrs {
+                policy.check_ip(host, addr.ip())?;
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 受 OutboundPolicy 限制的 HTTP 客户端，用于动态内容、条件探测和任务回调
+#[derive(Clone)]
+pub struct OutboundClient {
+    client: Client,
+    policy: Arc<OutboundPolicy>,
+}
+
+impl OutboundClient {
+    pub fn new(policy: Arc<OutboundPolicy>, builder: ClientBuilder) -> anyhow::Result<Self> {
+        let redirects = Arc::clone(&policy);
+        let client = builder
+            .dns_resolver(Arc::new(GuardedResolver(Arc::clone(&policy))))
+            .redirect(Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    return attempt.error("too many redirects");
+                }
+                match redirects.check_literal(attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(e) => attempt.error(e),
+                }
+            }))
+            .build()?;
+        Ok(Self { client, policy })
+    }
+
+    pub fn policy(&self) -> &OutboundPolicy {
+        &self.policy
+    }
+
+    pub fn get(&self, url: &str) -> anyhow::Result<RequestBuilder> {
+        self.policy.check_literal(&Url::parse(url)?)?;
+        Ok(self.client.get(url))
+    }
+
+    pub fn post(&self, url: &str) -> anyhow::Result<RequestBuilder> {
+        self.policy.check_literal(&Url::parse(url)?)?;
+        Ok(self.client.post(url))
+    }
+}
+
+/// 读取响应体，超过 MAX_BODY_BYTES 时中止
+pub async fn read_body(mut response: Response) -> anyhow::Result<Vec<u8>> {
+    let too_large = || anyhow::anyhow!("response body exceeds {} bytes", MAX_BODY_BYTES);
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_BODY_BYTES as u64)
+    {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{response::Redirect, routing::get, Router};
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn policy(allowed: &[&str]) -> OutboundPolicy {
+        OutboundPolicy::new(allowed.iter().map(|net| net.parse().unwrap()).collect())
+    }
+
+    /// 在 127.0.0.1 上启动测试服务，返回地址
+    async fn serve(app: Router) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    fn client(allowed: &[&str]) -> OutboundClient {
+        OutboundClient::new(Arc::new(policy(allowed)), Client::builder()).unwrap()
+    }
+
+    #[test]
+    fn detects_internal_addresses() {
+        for value in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "100.127.255.255",
+            "0.0.0.0",
+            "0.1.2.3",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(is_internal(ip(value)), "{}", value);
+        }
+        for value in ["8.8.8.8", "100.128.0.1", "172.32.0.1", "2001:4860:4860::8888", "::ffff:8.8.8.8"] {
+            assert!(!is_internal(ip(value)), "{}", value);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_internal_literal_urls() {
+        let policy = policy(&[]);
+        for url in [
+            "http://127.0.0.1/",
+            "http://10.0.0.1:8080/status",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.64.0.1/",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            // 十进制写法会被规范化为 127.0.0.1
+            "http://2130706433/",
+        ] {
+            let err = policy.check_url(url).await.unwrap_err();
+            assert!(err.to_string().contains("internal address"), "{}: {}", url, err);
+        }
+        assert!(policy.check_url("not a url").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_names_resolving_to_internal_addresses() {
+        let err = policy(&[]).check_url("http://localhost:8080/").await.unwrap_err();
+        assert!(err.to_string().contains("resolves to internal address"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn allowed_networks_are_permitted() {
+        let policy = policy(&["127.0.0.0/8", "10.0.0.5"]);
+        assert!(policy.check_url("http://127.0.0.1/").await.is_ok());
+        assert!(policy.check_url("http://[::ffff:127.0.0.1]/").await.is_ok());
+        assert!(policy.check_url("http://10.0.0.5/").await.is_ok());
+        assert!(policy.check_url("http://10.0.0.6/").await.is_err());
+    }
+
+    #[test]
+    fn client_rejects_internal_literal_before_sending() {
+        let client = client(&[]);
+        assert!(client.get("http://169.254.169.254/").is_err());
+        assert!(client.post("http://192.168.1.1/hook").is_err());
+        assert!(client.get("http://8.8.8.8/").is_ok());
+    }
+
+    #[tokio::test]
+    async fn redirect_to_internal_literal_is_blocked() {
+        let app = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/metadata", get(|| async { Redirect::temporary("http://169.254.169.254/latest/") }))
+            .route("/loop", get(|| async { Redirect::temporary("/loop") }));
+        let addr = serve(app).await;
+        // 只放行测试服务所在的地址
+        let client = client(&["127.0.0.1/32"]);
+
+        let response = client.get(&format!("http://{}/ok", addr)).unwrap().send().await.unwrap();
+        assert_eq!(read_body(response).await.unwrap(), b"ok");
+
+        let err = client
+            .get(&format!("http://{}/metadata", addr))
+            .unwrap()
+            .send()
+            .await
+            .unwrap_err();
+        let message = format!("{:?}", err);
+        assert!(err.is_redirect() && message.contains("internal address"), "{}", message);
+
+        let err = client.get(&format!("http://{}/loop", addr)).unwrap().send().await.unwrap_err();
+        assert!(err.is_redirect(), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn read_body_stops_at_the_limit() {
+        let app = Router::new()
+            .route("/fits", get(|| async { "x".repeat(MAX_BODY_BYTES) }))
+            .route("/big", get(|| async { "x".repeat(MAX_BODY_BYTES + 1) }));
+        let addr = serve(app).await;
+        let client = client(&["127.0.0.1/32"]);
+        let fetch = |path: &'static str| {
+            let request = client.get(&format!("http://{}{}", addr, path)).unwrap();
+            async move { read_body(request.send().await.unwrap()).await }
+        };
+        assert_eq!(fetch("/fits").await.unwrap().len(), MAX_BODY_BYTES);
+        assert!(fetch("/big").await.is_err());
+    }
+}
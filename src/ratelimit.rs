@@ -0,0 +1,341 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth::client_ip;
+use crate::metrics::{METRICS, RATE_LIMITED};
+use crate::notify::DEFAULT_DEVICE;
+use crate::tokens::Principal;
+
+// 清理已回满的令牌桶的间隔
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 令牌桶限流：每分钟补充 per_minute 次，最多累积 burst 次（默认等于 per_minute）
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+}
+
+impl RateLimit {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.per_minute == 0 || self.burst == Some(0) {
+            return Err(anyhow::anyhow!("per_minute and burst must be greater than 0"));
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> f64 {
+        f64::from(self.burst.unwrap_or(self.per_minute))
+    }
+
+    // 每秒补充的次数
+    fn rate(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+/// 限流配置，未设置的项不限制
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    // 每个来源 IP 的全部请求
+    #[serde(default)]
+    pub per_ip: Option<RateLimit>,
+    // 每个令牌的全部请求，令牌自身的 rate_limit 优先
+    #[serde(default)]
+    pub per_token: Option<RateLimit>,
+    // 按路由（如 "/notify"、"/jobs/:job_id"）限制每个令牌的请求
+    #[serde(default)]
+    pub routes: HashMap<String, RateLimit>,
+    // 每个设备的即时推送
+    #[serde(default)]
+    pub per_device: Option<RateLimit>,
+    // 每个令牌最多拥有的未完成任务数（0 表示不限制），令牌自身的 max_active_jobs 优先
+    #[serde(default)]
+    pub max_active_jobs: usize,
+}
+
+impl RateLimitConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let named = [
+            ("per_ip", self.per_ip.as_ref()),
+            ("per_token", self.per_token.as_ref()),
+            ("per_device", self.per_device.as_ref()),
+        ];
+        for (name, limit) in named {
+            if let Some(limit) = limit {
+                limit.validate().map_err(|e| anyhow::anyhow!("rate_limit.{}: {}", name, e))?;
+            }
+        }
+        for (route, limit) in &self.routes {
+            limit
+                .validate()
+                .map_err(|e| anyhow::anyhow!("rate_limit.routes.\"{}\": {}", route, e))?;
+        }
+        Ok(())
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    // 从空桶回满所需时长，超过后可以丢弃
+    refill: Duration,
+}
+
+struct Buckets {
+    entries: HashMap<String, Bucket>,
+    last_prune: Instant,
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(Buckets {
+                entries: HashMap::new(),
+                last_prune: Instant::now(),
+            }),
+        }
+    }
+
+    /// 同时检查多个令牌桶：全部有余量时各扣一次，否则都不扣，返回受限的维度和需等待的时长
+    fn check(&self, checks: &[(&'static str, String, RateLimit)]) -> Result<(), (&'static str, Duration)> {
+        self.check_at(checks, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        checks: &[(&'static str, String, RateLimit)],
+        now: Instant,
+    ) -> Result<(), (&'static str, Duration)> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if now.duration_since(buckets.last_prune) >= PRUNE_INTERVAL {
+            buckets
+                .entries
+                .retain(|_, bucket| now.duration_since(bucket.updated) < bucket.refill);
+            buckets.last_prune = now;
+        }
+
+        let mut limited: Option<(&'static str, Duration)> = None;
+        for (kind, key, limit) in checks {
+            let bucket = buckets.entries.entry(key.clone()).or_insert(Bucket {
+                tokens: limit.capacity(),
+                updated: now,
+                refill: Duration::from_secs_f64(limit.capacity() / limit.rate()),
+            });
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * limit.rate()).min(limit.capacity());
+            bucket.updated = now;
+            if bucket.tokens < 1.0 {
+                let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / limit.rate());
+                if limited.is_none_or(|(_, longest)| wait > longest) {
+                    limited = Some((kind, wait));
+                }
+            }
+        }
+        if let Some(limited) = limited {
+            return Err(limited);
+        }
+        for (_, key, _) in checks {
+            if let Some(bucket) = buckets.entries.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查设备的即时推送频率，受限时返回 429 响应
+    pub fn check_device(&self, device: Option<&str>) -> Option<Response> {
+        let limit = self.config.per_device?;
+        let device = device.unwrap_or(DEFAULT_DEVICE);
+        self.check(&[("device", format!("device:{}", device), limit)])
+            .err()
+            .map(|(kind, wait)| rate_limited(kind, wait))
+    }
+
+    /// 令牌可拥有的未完成任务上限
+    pub fn job_quota(&self, principal: &Principal) -> Option<usize> {
+        let max = principal.max_active_jobs.unwrap_or(self.config.max_active_jobs);
+        (max > 0).then_some(max)
+    }
+}
+
+/// 429 响应，Retry-After 向上取整，避免客户端过早重试
+pub fn too_many_requests(message: &'static str, retry_after: Duration) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, message).into_response();
+    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    if let Ok(value) = HeaderValue::from_str(&secs.to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+fn rate_limited(kind: &str, retry_after: Duration) -> Response {
+    METRICS.inc(RATE_LIMITED, &[("limit", kind)]);
+    too_many_requests("Rate limit exceeded, try again later", retry_after)
+}
+
+/// 按来源 IP、令牌和路由限流，需在 auth_middleware 之后执行
+pub async fn rate_limit_middleware(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = &limiter.config;
+    let mut checks = Vec::new();
+    if let (Some(limit), Some(ip)) = (config.per_ip, client_ip(&request)) {
+        checks.push(("ip", format!("ip:{}", ip), limit));
+    }
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        if let Some(limit) = principal.rate_limit.or(config.per_token) {
+            checks.push(("token", format!("token:{}", principal.id), limit));
+        }
+        if let Some(route) = request.extensions().get::<MatchedPath>()
+            && let Some(limit) = config.routes.get(route.as_str())
+        {
+            checks.push((
+                "route",
+                format!("route:{}:{}", principal.id, route.as_str()),
+                *limit,
+            ));
+        }
+    }
+
+    match limiter.check(&checks) {
+        Ok(()) => next.run(request).await,
+        Err((kind, wait)) => rate_limited(kind, wait),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(per_minute: u32, burst: u32) -> RateLimit {
+        RateLimit {
+            per_minute,
+            burst: Some(burst),
+        }
+    }
+
+    fn token(limit: RateLimit) -> (&'static str, String, RateLimit) {
+        ("token", "token:a".to_string(), limit)
+    }
+
+    fn ip(limit: RateLimit) -> (&'static str, String, RateLimit) {
+        ("ip", "ip:192.0.2.1".to_string(), limit)
+    }
+
+    #[test]
+    fn allows_burst_then_limits() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let start = Instant::now();
+        // 每秒补充 1 次，最多累积 3 次
+        let checks = [token(limit(60, 3))];
+        for _ in 0..3 {
+            assert!(limiter.check_at(&checks, start).is_ok());
+        }
+        let (kind, wait) = limiter.check_at(&checks, start).unwrap_err();
+        assert_eq!(kind, "token");
+        assert_eq!(wait, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn refills_over_time_up_to_capacity() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let start = Instant::now();
+        let checks = [token(limit(60, 3))];
+        for _ in 0..3 {
+            assert!(limiter.check_at(&checks, start).is_ok());
+        }
+        let (_, wait) = limiter
+            .check_at(&checks, start + Duration::from_millis(500))
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        assert!(limiter.check_at(&checks, start + Duration::from_secs(1)).is_ok());
+        assert!(limiter.check_at(&checks, start + Duration::from_secs(1)).is_err());
+
+        // 空闲很久后也只累积到 burst
+        let later = start + Duration::from_secs(30);
+        for _ in 0..3 {
+            assert!(limiter.check_at(&checks, later).is_ok());
+        }
+        assert!(limiter.check_at(&checks, later).is_err());
+    }
+
+    #[test]
+    fn deducts_all_buckets_or_none() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let start = Instant::now();
+        let both = [ip(limit(1, 1)), token(limit(60, 5))];
+        assert!(limiter.check_at(&both, start).is_ok());
+        // ip 桶已空，整个请求被拒绝，token 桶不扣
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(&both, start).unwrap_err().0, "ip");
+        }
+        let token_only = [token(limit(60, 5))];
+        for _ in 0..4 {
+            assert!(limiter.check_at(&token_only, start).is_ok());
+        }
+        assert!(limiter.check_at(&token_only, start).is_err());
+    }
+
+    #[test]
+    fn reports_the_longest_wait() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let start = Instant::now();
+        // ip 每 60 秒补充 1 次，token 每秒 1 次
+        let both = [token(limit(60, 1)), ip(limit(1, 1))];
+        assert!(limiter.check_at(&both, start).is_ok());
+        let (kind, wait) = limiter.check_at(&both, start).unwrap_err();
+        assert_eq!(kind, "ip");
+        assert_eq!(wait, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn prunes_refilled_buckets() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let start = Instant::now();
+        assert!(limiter.check_at(&[token(limit(60, 3))], start).is_ok());
+        let later = start + PRUNE_INTERVAL;
+        assert!(limiter.check_at(&[ip(limit(60, 3))], later).is_ok());
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(!buckets.entries.contains_key("token:a"));
+        assert!(buckets.entries.contains_key("ip:192.0.2.1"));
+    }
+
+    #[test]
+    fn retry_after_rounds_up() {
+        let retry_after = |wait: Duration| {
+            too_many_requests("limited", wait).headers()[header::RETRY_AFTER]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(retry_after(Duration::from_secs(2)), "2");
+        assert_eq!(retry_after(Duration::from_millis(1200)), "2");
+        assert_eq!(retry_after(Duration::from_nanos(1)), "1");
+        assert_eq!(retry_after(Duration::ZERO), "0");
+    }
+
+    #[test]
+    fn rejects_zero_limits() {
+        assert!(limit(0, 1).validate().is_err());
+        assert!(limit(1, 0).validate().is_err());
+        assert!(RateLimit { per_minute: 1, burst: None }.validate().is_ok());
+    }
+}
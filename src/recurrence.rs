@@ -0,0 +1,216 @@
+use chrono::{DateTime, Utc};
+use rrule::{RRuleSet, Tz};
+use std::time::Duration;
+
+// 单次计算的最大重复次数，防止恶意规则耗尽资源
+const MAX_OCCURRENCES: u16 = 1000;
+
+// 标准 cron 中星期字段的数字（0 和 7 都表示周日）对应的名称
+const WEEKDAY_NAMES: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+/// 将 cron 表达式统一为 6 位格式（秒 分 时 日 月 星期）：
+/// 支持 @hourly、@daily 等宏和标准 5 位 cron，6 位表达式原样返回
+pub fn normalize_cron(expr: &str) -> anyhow::Result<String> {
+    let expr = expr.trim();
+    let macro_expr = match expr.to_ascii_lowercase().as_str() {
+        "@yearly" | "@annually" => Some("0 0 0 1 1 *"),
+        "@monthly" => Some("0 0 0 1 * *"),
+        "@weekly" => Some("0 0 0 * * SUN"),
+        "@daily" | "@midnight" => Some("0 0 0 * * *"),
+        "@hourly" => Some("0 0 * * * *"),
+        _ => None,
+    };
+    if let Some(normalized) = macro_expr {
+        return Ok(normalized.to_string());
+    }
+    if expr.starts_with('@') {
+        return Err(anyhow::anyhow!(
+            "Unknown cron macro '{}': use @yearly, @monthly, @weekly, @daily or @hourly",
+            expr
+        ));
+    }
+
+    let parts: Vec<&str> = expr.split_whitespace().collect();
+    match parts.len() {
+        6 => Ok(parts.join(" ")),
+        // 标准 5 位 cron：补秒字段，星期数字按 0=周日 换成名称
+        5 => Ok(format!(
+            "0 {} {} {} {} {}",
+            parts[0],
+            parts[1],
+            parts[2],
+            parts[3],
+            weekday_numbers_to_names(parts[4])?
+        )),
+        n => Err(anyhow::anyhow!(
+            "Invalid cron expression: expected 5 parts (min hour day month day_of_week) \
+             or 6 parts (sec min hour day month day_of_week), got {}",
+            n
+        )),
+    }
+}
+
+fn weekday_numbers_to_names(field: &str) -> anyhow::Result<String> {
+    let convert = |value: &str| -> anyhow::Result<String> {
+        match value.parse::<usize>() {
+            Ok(n) if n < WEEKDAY_NAMES.len() => Ok(WEEKDAY_NAMES[n].to_string()),
+            Ok(n) => Err(anyhow::anyhow!("Invalid day of week {}: expected 0-7", n)),
+            Err(_) => Ok(value.to_string()),
+        }
+    };
+
+    field
+        .split(',')
+        .map(|item| {
+            // 步长部分（"/" 之后）保持数字
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (item, None),
+            };
+            let range = match range.split_once('-') {
+                Some((from, to)) => format!("{}-{}", convert(from)?, convert(to)?),
+                None => convert(range)?,
+            };
+            Ok(match step {
+                Some(step) => format!("{}/{}", range, step),
+                None => range,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(|items| items.join(","))
+}
+
+/// 固定间隔的下一次触发时间：anchor + k * interval 中严格晚于 after 的最早时刻
+pub fn interval_next_after(
+    interval: Duration,
+    anchor: DateTime<Utc>,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let interval = chrono::Duration::from_std(interval).ok()?;
+    if after < anchor {
+        return Some(anchor);
+    }
+    let step = interval.num_milliseconds().max(1);
+    let elapsed = (after - anchor).num_milliseconds();
+    let periods = elapsed / step + 1;
+    let offset = chrono::Duration::try_milliseconds(periods.checked_mul(step)?)?;
+    anchor.checked_add_signed(offset)
+}
+
+/// 解析 RFC 5545 重复规则，支持 DTSTART、RRULE、RDATE、EXDATE 多行写法。
+/// 只写 "FREQ=..." 时自动补上 "RRULE:" 前缀，未指定 DTSTART 时使用 default_start
+pub fn parse_rrule(input: &str, default_start: DateTime<Utc>) -> anyhow::Result<(RRuleSet, String)> {
+    let mut lines: Vec<String> = input
+        .replace("\\n", "\n")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let upper = line.to_ascii_uppercase();
+            let is_property = ["DTSTART", "RRULE", "RDATE", "EXDATE", "EXRULE"]
+                .iter()
+                .any(|name| upper.starts_with(name));
+            if is_property {
+                line.to_string()
+            } else {
+                format!("RRULE:{}", line)
+            }
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return Err(anyhow::anyhow!("rrule must not be empty"));
+    }
+    if !lines
+        .iter()
+        .any(|line| line.to_ascii_uppercase().starts_with("DTSTART"))
+    {
+        lines.insert(
+            0,
+            format!("DTSTART:{}", default_start.format("%Y%m%dT%H%M%SZ")),
+        );
+    }
+
+    let text = lines.join("\n");
+    let set: RRuleSet = text
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid rrule: {}", e))?;
+    Ok((set, text))
+}
+
+/// 返回严格晚于 after 的下一次发生时间
+pub fn rrule_next_after(set: &RRuleSet, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    rrule_upcoming(set, after, 1).into_iter().next()
+}
+
+/// 返回严格晚于 after 的最多 limit 次发生时间
+pub fn rrule_upcoming(set: &RRuleSet, after: DateTime<Utc>, limit: u16) -> Vec<DateTime<Utc>> {
+    let limit = limit.min(MAX_OCCURRENCES);
+    set.clone()
+        .after(after.with_timezone(&Tz::UTC))
+        .all(limit.saturating_add(1))
+        .dates
+        .into_iter()
+        .map(|dt| dt.with_timezone(&Utc))
+        .filter(|dt| *dt > after)
+        .take(limit as usize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn normalizes_five_field_cron() {
+        assert_eq!(normalize_cron("30 9 * * 1-5").unwrap(), "0 30 9 * * MON-FRI");
+        assert_eq!(normalize_cron("  */15 * * * *  ").unwrap(), "0 */15 * * * *");
+        assert_eq!(normalize_cron("0 8 * * MON").unwrap(), "0 0 8 * * MON");
+    }
+
+    #[test]
+    fn maps_weekday_zero_and_seven_to_sunday() {
+        assert_eq!(normalize_cron("0 9 * * 0").unwrap(), "0 0 9 * * SUN");
+        assert_eq!(normalize_cron("0 9 * * 7").unwrap(), "0 0 9 * * SUN");
+        assert_eq!(normalize_cron("0 9 * * 0,6").unwrap(), "0 0 9 * * SUN,SAT");
+        assert_eq!(normalize_cron("0 9 * * 1-5/2").unwrap(), "0 0 9 * * MON-FRI/2");
+        assert!(normalize_cron("0 9 * * 8").is_err());
+    }
+
+    #[test]
+    fn keeps_six_field_cron_and_expands_macros() {
+        assert_eq!(normalize_cron("0 0 9 * * 1").unwrap(), "0 0 9 * * 1");
+        assert_eq!(normalize_cron("@daily").unwrap(), "0 0 0 * * *");
+        assert_eq!(normalize_cron("@WEEKLY").unwrap(), "0 0 0 * * SUN");
+        assert_eq!(normalize_cron("@annually").unwrap(), "0 0 0 1 1 *");
+        assert!(normalize_cron("@fortnightly").is_err());
+        assert!(normalize_cron("* * *").is_err());
+    }
+
+    #[test]
+    fn interval_steps_from_anchor() {
+        let every = Duration::from_secs(15 * 60);
+        let anchor = at(9, 0, 0);
+        // 尚未到达 anchor 时以 anchor 为第一次
+        assert_eq!(interval_next_after(every, anchor, at(8, 0, 0)), Some(anchor));
+        assert_eq!(interval_next_after(every, anchor, anchor), Some(at(9, 15, 0)));
+        assert_eq!(interval_next_after(every, anchor, at(9, 14, 59)), Some(at(9, 15, 0)));
+        assert_eq!(interval_next_after(every, anchor, at(9, 15, 0)), Some(at(9, 30, 0)));
+        assert_eq!(interval_next_after(every, anchor, at(10, 7, 0)), Some(at(10, 15, 0)));
+    }
+
+    #[test]
+    fn interval_overflow_returns_none() {
+        let anchor = at(9, 0, 0);
+        let huge = Duration::from_secs(9_000_000_000_000_000);
+        assert_eq!(interval_next_after(huge, anchor, at(10, 0, 0)), None);
+        // 未超出 TimeDelta，但结果超出 DateTime 范围
+        let long = Duration::from_secs(300_000 * 365 * 24 * 60 * 60);
+        assert_eq!(interval_next_after(long, anchor, at(10, 0, 0)), None);
+    }
+}
@@ -1,25 +1,86 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Timelike, Utc};
+use reqwest::Client;
+use rrule::RRuleSet;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio_cron_scheduler::{Job, JobScheduler};
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::notify::{Notifier, NotifyRequest};
+use crate::callback::{self, CallbackDispatcher};
+use crate::condition::{Condition, STATE_UNKNOWN};
+use crate::content::ContentSource;
+use crate::duration::parse_duration;
+use crate::events::{EventBus, EventKind};
+use crate::metrics::{JOB_FIRES, METRICS};
+use crate::natural;
+use crate::outbound::{OutboundClient, OutboundPolicy};
+use crate::notify::{BarkResponse, Notifier, NotifyRequest};
+use crate::recurrence::{interval_next_after, normalize_cron, parse_rrule, rrule_next_after};
+
+/// 循环任务的触发规则：cron、rrule、every 三选一
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleSpec {
+    // Cron expression: "0 */5 * * * *" (every 5 minutes)
+    // or "0 0 9 * * *" (every day at 9:00)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    // RFC 5545 重复规则，与 cron 二选一，如 "FREQ=MONTHLY;BYDAY=2TU;BYHOUR=9"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rrule: Option<String>,
+    // 固定间隔，与 cron、rrule 三选一，如 "90s"、"15m"、"2h"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<String>,
+    // 间隔的对齐时间点，触发时间为 align_to + k * every；默认从创建时间开始
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub align_to: Option<DateTime<Utc>>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduleRequest {
     #[serde(flatten)]
     pub notify: NotifyRequest,
-    // Cron expression: "0 */5 * * * *" (every 5 minutes)
-    // or "0 0 9 * * *" (every day at 9:00)
-    pub cron: String,
+    #[serde(flatten)]
+    pub schedule: ScheduleSpec,
     // 最大执行次数，达到后自动删除。不设置或0表示无限次
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_count: Option<u32>,
+    // 触发时动态获取内容的数据源
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_source: Option<ContentSource>,
+    // 触发条件，满足时才发送通知
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+    // 客户端自定义的任务标识，同时作为幂等键防止重试时重复创建
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_job_id: Option<String>,
+    // 标签，用于筛选任务
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // 自定义元数据（JSON 对象），如创建任务的会话 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Map<String, Value>>,
+    // 开始时间，之前的触发直接跳过
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<DateTime<Utc>>,
+    // 结束时间，到达后任务停止并从列表移除
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<DateTime<Utc>>,
+    // 创建后多久过期，如 "14d"、"2h"；与 end_at 同时设置时取较早者
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_after: Option<String>,
+    // 任务执行结果、完成和取消时回调的地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    // 回调签名密钥，不设置时使用服务端配置的 callback_secret
+    #[serde(default, skip_serializing)]
+    pub callback_secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,151 +88,884 @@ pub struct OneTimeRequest {
     #[serde(flatten)]
     pub notify: NotifyRequest,
     // ISO 8601 format: "2024-01-15T09:00:00Z"
-    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<DateTime<Utc>>,
+    // 自然语言时间，与 at 二选一，如 "明天早上九点"、"in 2 hours"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    // 解析 when 使用的 IANA 时区，默认 UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    // 触发时动态获取内容的数据源
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_source: Option<ContentSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_job_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Map<String, Value>>,
+    // 任务执行结果、完成和取消时回调的地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    // 回调签名密钥，不设置时使用服务端配置的 callback_secret
+    #[serde(default, skip_serializing)]
+    pub callback_secret: Option<String>,
+}
+
+impl ScheduleRequest {
+    /// 任务触发时会访问的外部地址及其字段名
+    pub fn outbound_urls(&self) -> Vec<(&'static str, &str)> {
+        let mut urls = Vec::new();
+        if let Some(source) = &self.content_source {
+            urls.push(("content_source.url", source.url.as_str()));
+        }
+        if let Some(condition) = &self.condition {
+            urls.push(("condition.url", condition.url.as_str()));
+        }
+        if let Some(url) = &self.callback_url {
+            urls.push(("callback_url", url.as_str()));
+        }
+        urls
+    }
+}
+
+impl OneTimeRequest {
+    /// 任务触发时会访问的外部地址及其字段名
+    pub fn outbound_urls(&self) -> Vec<(&'static str, &str)> {
+        let mut urls = Vec::new();
+        if let Some(source) = &self.content_source {
+            urls.push(("content_source.url", source.url.as_str()));
+        }
+        if let Some(url) = &self.callback_url {
+            urls.push(("callback_url", url.as_str()));
+        }
+        urls
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Cron,
+    Rrule,
+    Interval,
+    Once,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobKind::Cron => "cron",
+            JobKind::Rrule => "rrule",
+            JobKind::Interval => "interval",
+            JobKind::Once => "once",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Active,
+    Paused,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledJob {
     pub id: String,
+    #[serde(rename = "type")]
+    pub kind: JobKind,
+    pub status: JobStatus,
     pub cron: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rrule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub every: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align_to: Option<DateTime<Utc>>,
     pub at: Option<DateTime<Utc>>,
     pub notify: NotifyRequest,
     pub created_at: DateTime<Utc>,
     // 最大执行次数，None 表示无限次
     pub max_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_source: Option<ContentSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_job_id: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Map<String, Value>>,
+    // 创建任务的令牌 ID，只有该令牌和 admin 可以查看、删除
+    pub owner: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<DateTime<Utc>>,
+    // 实际结束时间（end_at 与 expires_after 中较早者）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
     // 用于序列化时隐藏，不暴露给客户端
     #[serde(skip)]
     pub cancelled: Arc<AtomicBool>,
+    // 暂停标志，暂停期间触发时跳过
+    #[serde(skip)]
+    pub paused: Arc<AtomicBool>,
+}
+
+/// 任务筛选条件，所有条件同时满足才匹配
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JobFilter {
+    pub tag: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<JobKind>,
+    pub status: Option<JobStatus>,
+    pub group: Option<String>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub owner: Option<String>,
+}
+
+impl JobFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tag.is_none()
+            && self.kind.is_none()
+            && self.status.is_none()
+            && self.group.is_none()
+            && self.created_before.is_none()
+            && self.created_after.is_none()
+            && self.owner.is_none()
+    }
+
+    pub fn matches(&self, job: &ScheduledJob) -> bool {
+        self.tag.as_ref().is_none_or(|tag| job.tags.contains(tag))
+            && self.kind.is_none_or(|kind| job.kind == kind)
+            && self.status.is_none_or(|status| job.status == status)
+            && self
+                .group
+                .as_ref()
+                .is_none_or(|group| job.notify.group.as_ref() == Some(group))
+            && self
+                .created_before
+                .is_none_or(|before| job.created_at < before)
+            && self.created_after.is_none_or(|after| job.created_at > after)
+            && self.owner.as_ref().is_none_or(|owner| job.owner == *owner)
+    }
+}
+
+/// 分页查询：按创建时间排序，cursor 为上一页最后一个任务的位置
+#[derive(Debug, Clone, Default)]
+pub struct JobQuery {
+    pub filter: JobFilter,
+    pub sort: SortOrder,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+pub struct JobPage {
+    pub jobs: Vec<ScheduledJob>,
+    pub next_cursor: Option<String>,
+}
+
+fn job_cursor(job: &ScheduledJob) -> String {
+    format!(
+        "{:020}_{}",
+        job.created_at.timestamp_nanos_opt().unwrap_or_default(),
+        job.id
+    )
+}
+
+fn validate_labels(tags: &[String], metadata: &Option<Map<String, Value>>) -> anyhow::Result<()> {
+    if tags.iter().any(|tag| tag.trim().is_empty()) {
+        return Err(anyhow::anyhow!("Tags must not be empty"));
+    }
+    if tags.len() > 32 {
+        return Err(anyhow::anyhow!("A job can have at most 32 tags"));
+    }
+    if let Some(metadata) = metadata
+        && serde_json::to_vec(metadata)?.len() > 16 * 1024
+    {
+        return Err(anyhow::anyhow!("Job metadata must be at most 16 KiB"));
+    }
+    Ok(())
+}
+
+/// 校验循环任务请求，不创建任务
+pub fn validate_cron_request(req: &ScheduleRequest) -> anyhow::Result<()> {
+    parse_schedule(&req.schedule, req.start_at, Utc::now())?;
+
+    if let Some(source) = &req.content_source {
+        source.validate()?;
+    }
+    if let Some(condition) = &req.condition {
+        condition.validate()?;
+    }
+    effective_end(req, Utc::now())?;
+    if let Some(url) = &req.callback_url {
+        callback::validate_url(url)?;
+    }
+    validate_labels(&req.tags, &req.metadata)
+}
+
+/// 计算循环任务的实际结束时间，并检查时间范围是否有效
+fn effective_end(
+    req: &ScheduleRequest,
+    created_at: DateTime<Utc>,
+) -> anyhow::Result<Option<DateTime<Utc>>> {
+    let expires_at = match &req.expires_after {
+        Some(expires_after) => {
+            let duration = chrono::Duration::from_std(parse_duration(expires_after)?)?;
+            let end = created_at
+                .checked_add_signed(duration)
+                .ok_or_else(|| anyhow::anyhow!("expires_after '{}' is too large", expires_after))?;
+            Some(end)
+        }
+        None => None,
+    };
+    let end = match (req.end_at, expires_at) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+
+    if let Some(end) = end {
+        if end <= created_at {
+            return Err(anyhow::anyhow!("end_at must be in the future"));
+        }
+        if let Some(start) = req.start_at
+            && start >= end
+        {
+            return Err(anyhow::anyhow!("start_at must be before end_at"));
+        }
+    }
+    Ok(end)
+}
+
+/// 令牌拥有的任务（暂停的也计入）再加 adding 个是否超出上限
+fn check_quota(
+    jobs: &HashMap<String, ScheduledJob>,
+    owner: &str,
+    max: Option<usize>,
+    adding: usize,
+) -> anyhow::Result<()> {
+    let Some(max) = max else {
+        return Ok(());
+    };
+    let owned = jobs.values().filter(|job| job.owner == owner).count();
+    if owned + adding > max {
+        return Err(anyhow::anyhow!(
+            "Active job limit reached: {} of {} jobs in use",
+            owned,
+            max
+        ));
+    }
+    Ok(())
+}
+
+/// 任务正常结束：停止并从列表移除，与达到 max_count 和一次性任务一致
+async fn mark_completed(
+    jobs: &RwLock<HashMap<String, ScheduledJob>>,
+    events: &EventBus,
+    job_id: &str,
+) {
+    if let Some(job) = jobs.write().await.remove(job_id) {
+        job.cancelled.store(true, Ordering::SeqCst);
+        events.publish(
+            EventKind::JobCompleted,
+            Some(job_id),
+            Some(&job.owner),
+            &job.tags,
+            Value::Null,
+        );
+    }
+}
+
+/// 校验一次性任务请求，不创建任务；when 会被解析并写回 at，返回触发时间
+pub fn validate_one_time_request(req: &mut OneTimeRequest) -> anyhow::Result<DateTime<Utc>> {
+    let now = Utc::now();
+    if let Some(when) = req.when.take() {
+        if req.at.is_some() {
+            return Err(anyhow::anyhow!("Use either 'at' or 'when', not both"));
+        }
+        let tz = natural::parse_timezone(req.timezone.as_deref())?;
+        let parsed = natural::parse(&when, now, tz)?;
+        if parsed.kind != JobKind::Once {
+            return Err(anyhow::anyhow!(
+                "'{}' describes a recurring schedule, use /schedule/cron instead",
+                when
+            ));
+        }
+        if parsed.ambiguous {
+            return Err(anyhow::anyhow!(
+                "'{}' is ambiguous ({}), pass 'at' or rephrase",
+                when,
+                parsed.notes.join("; ")
+            ));
+        }
+        req.at = Some(parsed.at);
+    }
+    let at = req
+        .at
+        .ok_or_else(|| anyhow::anyhow!("Either 'at' or 'when' is required"))?;
+    if at <= now {
+        return Err(anyhow::anyhow!(
+            "Scheduled time must be in the future. Now: {}, Scheduled: {}",
+            now, at
+        ));
+    }
+
+    if let Some(source) = &req.content_source {
+        source.validate()?;
+    }
+    if let Some(url) = &req.callback_url {
+        callback::validate_url(url)?;
+    }
+    validate_labels(&req.tags, &req.metadata)?;
+    Ok(at)
+}
+
+/// 循环任务的触发规则
+pub enum RecurringSchedule {
+    // 统一为 6 位格式的 cron 表达式
+    Cron(String, cron::Schedule),
+    RRule(String, RRuleSet),
+    Interval {
+        every: String,
+        interval: std::time::Duration,
+        anchor: DateTime<Utc>,
+    },
+}
+
+impl RecurringSchedule {
+    /// 严格晚于 after 的下一次触发时间
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            RecurringSchedule::Cron(_, schedule) => schedule.after(&after).next(),
+            RecurringSchedule::RRule(_, set) => rrule_next_after(set, after),
+            RecurringSchedule::Interval {
+                interval, anchor, ..
+            } => interval_next_after(*interval, *anchor, after),
+        }
+    }
+
+    /// 返回严格晚于 after 的最多 limit 次触发时间
+    pub fn upcoming(&self, after: DateTime<Utc>, limit: usize) -> Vec<DateTime<Utc>> {
+        let mut runs = Vec::with_capacity(limit);
+        let mut cursor = after;
+        while runs.len() < limit
+            && let Some(next) = self.next_after(cursor)
+        {
+            runs.push(next);
+            cursor = next;
+        }
+        runs
+    }
+
+    /// 规范化后的规则文本
+    pub fn expression(&self) -> &str {
+        match self {
+            RecurringSchedule::Cron(expr, _) => expr,
+            RecurringSchedule::RRule(text, _) => text,
+            RecurringSchedule::Interval { every, .. } => every,
+        }
+    }
+
+    pub fn kind(&self) -> JobKind {
+        match self {
+            RecurringSchedule::Cron(..) => JobKind::Cron,
+            RecurringSchedule::RRule(..) => JobKind::Rrule,
+            RecurringSchedule::Interval { .. } => JobKind::Interval,
+        }
+    }
+}
+
+/// 解析触发规则；start_at 用作未指定 DTSTART 的 RRULE 的起点
+pub fn parse_schedule(
+    spec: &ScheduleSpec,
+    start_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+) -> anyhow::Result<RecurringSchedule> {
+    let specified = [spec.cron.is_some(), spec.rrule.is_some(), spec.every.is_some()]
+        .iter()
+        .filter(|set| **set)
+        .count();
+    if specified != 1 {
+        return Err(anyhow::anyhow!(
+            "Specify exactly one of cron, rrule or every"
+        ));
+    }
+    if spec.align_to.is_some() && spec.every.is_none() {
+        return Err(anyhow::anyhow!("align_to can only be used with every"));
+    }
+
+    if let Some(cron) = &spec.cron {
+        let normalized = normalize_cron(cron)?;
+        let schedule = normalized
+            .parse::<cron::Schedule>()
+            .map_err(|e| anyhow::anyhow!("Invalid cron expression: {}", e))?;
+        return Ok(RecurringSchedule::Cron(normalized, schedule));
+    }
+
+    if let Some(rrule) = &spec.rrule {
+        // 未指定 DTSTART 时从 start_at 或下一整秒开始，保证第一次发生不会落在过去
+        let default_start = match start_at {
+            Some(start) => start.with_nanosecond(0).unwrap_or(start),
+            None => {
+                created_at.with_nanosecond(0).unwrap_or(created_at) + chrono::Duration::seconds(1)
+            }
+        };
+        let (set, text) = parse_rrule(rrule, default_start)?;
+        return Ok(RecurringSchedule::RRule(text, set));
+    }
+
+    let every = spec.every.clone().unwrap_or_default();
+    let interval = parse_duration(&every)?;
+    Ok(RecurringSchedule::Interval {
+        every,
+        interval,
+        anchor: spec.align_to.unwrap_or(created_at),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TickOutcome {
+    Continue,
+    Finished,
+}
+
+/// 循环任务每次触发共享的运行状态
+struct RecurringRun {
+    job_id: String,
+    kind: JobKind,
+    owner: String,
+    notifier: Arc<Notifier>,
+    // 获取动态内容和探测条件，受 outbound_allowed_networks 限制
+    outbound: OutboundClient,
+    jobs: Arc<RwLock<HashMap<String, ScheduledJob>>>,
+    events: Arc<EventBus>,
+    tags: Vec<String>,
+    notify_req: NotifyRequest,
+    content_source: Option<ContentSource>,
+    condition: Option<Condition>,
+    // 条件任务上一次的探测状态
+    condition_state: AtomicU8,
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    current_count: AtomicU32,
+    max_count: Option<u32>,
+    start_at: Option<DateTime<Utc>>,
+    end_at: Option<DateTime<Utc>>,
+}
+
+impl RecurringRun {
+    /// 执行一次触发；next 为之后的下一次触发时间，None 表示没有后续触发
+    async fn tick(&self, next: Option<DateTime<Utc>>) -> TickOutcome {
+        let job_id = &self.job_id;
+
+        // 检查是否已被取消
+        if self.cancelled.load(Ordering::SeqCst) {
+            info!("Job {} is cancelled, skipping execution", job_id);
+            return TickOutcome::Finished;
+        }
+
+        // 超过结束时间：标记完成
+        let now = Utc::now();
+        if let Some(end) = self.end_at
+            && now >= end
+        {
+            info!("Job {} passed end_at ({}), completing", job_id, end);
+            mark_completed(&self.jobs, &self.events, job_id).await;
+            return TickOutcome::Finished;
+        }
+
+        if self.paused.load(Ordering::SeqCst) {
+            info!("Job {} is paused, skipping execution", job_id);
+            return TickOutcome::Continue;
+        }
+        if let Some(start) = self.start_at
+            && now < start
+        {
+            info!("Job {} has not reached start_at ({}), skipping", job_id, start);
+            return TickOutcome::Continue;
+        }
+
+        // 检查触发条件，不满足时本次不计数也不发送
+        let notify_req = match &self.condition {
+            Some(condition) => {
+                let result = condition.evaluate(&self.outbound).await;
+                match condition.decide(&result, &self.condition_state, &self.notify_req) {
+                    Some(req) => req,
+                    None => {
+                        info!("Job {} condition not met, skipping notification", job_id);
+                        return TickOutcome::Continue;
+                    }
+                }
+            }
+            None => self.notify_req.clone(),
+        };
+
+        // 增加计数
+        let count = self.current_count.fetch_add(1, Ordering::SeqCst) + 1;
+        info!("Executing scheduled job {} (count: {})", job_id, count);
+        self.events.publish(
+            EventKind::JobFired,
+            Some(job_id),
+            Some(&self.owner),
+            &self.tags,
+            serde_json::json!({ "count": count }),
+        );
+        METRICS.inc(JOB_FIRES, &[("type", self.kind.as_str())]);
+
+        // 获取动态内容
+        let notify_req = match &self.content_source {
+            Some(source) => source.resolve(&self.outbound, &notify_req).await,
+            None => notify_req,
+        };
+
+        // 发送通知
+        match self.notifier.send_via(&notify_req, self.kind.as_str()).await {
+            Ok(resp) => {
+                info!("Job {} executed successfully (count: {})", job_id, count);
+                publish_delivery(&self.events, job_id, &self.owner, &self.tags, Ok(&resp));
+            }
+            Err(e) => {
+                error!("Failed to send scheduled notification for job {}: {}", job_id, e);
+                publish_delivery(&self.events, job_id, &self.owner, &self.tags, Err(&e));
+            }
+        }
+
+        // 检查是否达到最大次数
+        if let Some(max) = self.max_count
+            && count >= max
+        {
+            info!("Job {} reached max count ({}), removing", job_id, max);
+            self.cancelled.store(true, Ordering::SeqCst);
+            self.jobs.write().await.remove(job_id);
+            self.events.publish(
+                EventKind::JobCompleted,
+                Some(job_id),
+                Some(&self.owner),
+                &self.tags,
+                serde_json::json!({ "count": count }),
+            );
+            return TickOutcome::Finished;
+        }
+
+        // 没有后续触发，或下一次触发已超过结束时间，提前完成
+        let finished = match (next, self.end_at) {
+            (None, _) => true,
+            (Some(next), Some(end)) => next >= end,
+            (Some(_), None) => false,
+        };
+        if finished {
+            info!("Job {} has no more runs, completing", job_id);
+            mark_completed(&self.jobs, &self.events, job_id).await;
+            return TickOutcome::Finished;
+        }
+        TickOutcome::Continue
+    }
+}
+
+/// 发布一次推送的结果事件
+fn publish_delivery(
+    events: &EventBus,
+    job_id: &str,
+    owner: &str,
+    tags: &[String],
+    result: Result<&BarkResponse, &anyhow::Error>,
+) {
+    match result {
+        Ok(resp) => events.publish(
+            EventKind::DeliverySucceeded,
+            Some(job_id),
+            Some(owner),
+            tags,
+            serde_json::json!({ "code": resp.code, "message": resp.message }),
+        ),
+        Err(e) => events.publish(
+            EventKind::DeliveryFailed,
+            Some(job_id),
+            Some(owner),
+            tags,
+            serde_json::json!({ "error": e.to_string() }),
+        ),
+    }
+}
+
+/// 为 RRULE / 固定间隔任务注册下一次触发的一次性任务，触发后再注册下一次
+fn schedule_next_tick(
+    scheduler: JobScheduler,
+    run: Arc<RecurringRun>,
+    schedule: Arc<RecurringSchedule>,
+    at: DateTime<Utc>,
+) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>> {
+    Box::pin(async move {
+        let delay = (at - Utc::now()).to_std().unwrap_or_default();
+        let job = Job::new_one_shot_async(delay, move |_uuid, l| {
+            let run = Arc::clone(&run);
+            let schedule = Arc::clone(&schedule);
+            Box::pin(async move {
+                let next = schedule.next_after(at.max(Utc::now()));
+                if run.tick(next).await == TickOutcome::Continue
+                    && let Some(next) = next
+                    && let Err(e) = schedule_next_tick(l, Arc::clone(&run), schedule, next).await
+                {
+                    error!("Failed to schedule next run for job {}: {}", run.job_id, e);
+                }
+            })
+        })?;
+        scheduler.add(job).await?;
+        Ok(())
+    })
 }
 
 pub struct NotificationScheduler {
     scheduler: JobScheduler,
     notifier: Arc<Notifier>,
+    // 获取动态内容和探测条件使用的 HTTP 客户端
+    outbound: OutboundClient,
     jobs: Arc<RwLock<HashMap<String, ScheduledJob>>>,
+    events: Arc<EventBus>,
+    callbacks: Arc<CallbackDispatcher>,
+    running: AtomicBool,
 }
 
 impl NotificationScheduler {
-    pub async fn new(notifier: Arc<Notifier>) -> anyhow::Result<Self> {
+    pub async fn new(
+        notifier: Arc<Notifier>,
+        events: Arc<EventBus>,
+        callbacks: Arc<CallbackDispatcher>,
+        outbound: Arc<OutboundPolicy>,
+    ) -> anyhow::Result<Self> {
         let scheduler = JobScheduler::new().await?;
         
         Ok(Self {
             scheduler,
             notifier,
+            outbound: OutboundClient::new(outbound, Client::builder())?,
             jobs: Arc::new(RwLock::new(HashMap::new())),
+            events,
+            callbacks,
+            running: AtomicBool::new(false),
         })
     }
 
     pub async fn start(&self) -> anyhow::Result<()> {
         self.scheduler.start().await?;
+        self.running.store(true, Ordering::SeqCst);
         info!("Scheduler started");
         Ok(())
     }
 
-    pub async fn add_cron_job(&self, req: ScheduleRequest) -> anyhow::Result<String> {
-        let job_id = Uuid::new_v4().to_string();
-        let notifier = Arc::clone(&self.notifier);
-        let notify_req = req.notify.clone();
-        let job_id_clone = job_id.clone();
-        let jobs = Arc::clone(&self.jobs);
-        let max_count = req.max_count;
+    /// 检查任务的目标设备是否已配置
+    pub fn check_device(&self, device: Option<&str>) -> anyhow::Result<()> {
+        self.notifier.check_device(device)
+    }
 
-        // Validate cron expression first
-        let cron_str = req.cron.clone();
-        let cron_parts: Vec<&str> = cron_str.split_whitespace().collect();
-        if cron_parts.len() != 6 {
-            return Err(anyhow::anyhow!(
-                "Invalid cron expression: expected 6 parts (sec min hour day month day_of_week), got {}",
-                cron_parts.len()
-            ));
+    /// 检查任务会访问的外部地址是否被允许
+    pub async fn check_outbound(&self, urls: &[(&'static str, &str)]) -> anyhow::Result<()> {
+        for (field, url) in urls {
+            self.outbound
+                .policy()
+                .check_url(url)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}: {}", field, e))?;
         }
+        Ok(())
+    }
+
+    /// 设置了回调地址时，确认有可用的签名密钥
+    pub fn check_callback(&self, url: Option<&str>, secret: Option<&str>) -> anyhow::Result<()> {
+        if url.is_some() {
+            self.callbacks.check_secret(secret)?;
+        }
+        Ok(())
+    }
+
+    /// 调度器已启动且内部任务存储仍可访问
+    pub async fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+            && self.scheduler.clone().time_till_next_job().await.is_ok()
+    }
+
+    /// 任务列表在 timeout 内可读时返回任务数
+    pub async fn job_count(&self, timeout: std::time::Duration) -> Option<usize> {
+        tokio::time::timeout(timeout, self.jobs.read())
+            .await
+            .ok()
+            .map(|jobs| jobs.len())
+    }
 
-        // 创建取消标志和计数器
+    /// 创建循环任务：cron 表达式或 RRULE 重复规则
+    pub async fn add_cron_job(
+        &self,
+        req: ScheduleRequest,
+        owner: &str,
+        quota: Option<usize>,
+    ) -> anyhow::Result<String> {
+        // Validate request first
+        validate_cron_request(&req)?;
+        self.check_device(req.notify.device.as_deref())?;
+        self.check_outbound(&req.outbound_urls()).await?;
+        self.check_callback(req.callback_url.as_deref(), req.callback_secret.as_deref())?;
+        let created_at = Utc::now();
+        let schedule = parse_schedule(&req.schedule, req.start_at, created_at)?;
+        let end_at = effective_end(&req, created_at)?;
+
+        let job_id = Uuid::new_v4().to_string();
         let cancelled = Arc::new(AtomicBool::new(false));
-        let cancelled_clone = Arc::clone(&cancelled);
-        let current_count = Arc::new(AtomicU32::new(0));
-
-        let job = Job::new_async(cron_str.as_str(), move |_uuid, _l| {
-            let notifier = Arc::clone(&notifier);
-            let notify_req = notify_req.clone();
-            let job_id = job_id_clone.clone();
-            let jobs = Arc::clone(&jobs);
-            let cancelled = Arc::clone(&cancelled_clone);
-            let current_count = Arc::clone(&current_count);
-            let max_count = max_count;
-            
-            Box::pin(async move {
-                // 检查是否已被取消
-                if cancelled.load(Ordering::SeqCst) {
-                    info!("Job {} is cancelled, skipping execution", job_id);
-                    return;
-                }
-                
-                // 增加计数
-                let count = current_count.fetch_add(1, Ordering::SeqCst) + 1;
-                info!("Executing scheduled job {} (count: {})", job_id, count);
-                
-                // 发送通知
-                match notifier.send(&notify_req).await {
-                    Ok(_) => info!("Job {} executed successfully (count: {})", job_id, count),
-                    Err(e) => error!("Failed to send scheduled notification for job {}: {}", job_id, e),
-                }
-                
-                // 检查是否达到最大次数
-                if let Some(max) = max_count {
-                    if count >= max {
-                        info!("Job {} reached max count ({}), removing", job_id, max);
-                        cancelled.store(true, Ordering::SeqCst);
-                        jobs.write().await.remove(&job_id);
-                        return;
-                    }
-                }
-            })
-        })?;
+        let paused = Arc::new(AtomicBool::new(false));
+        if let Some(url) = req.callback_url.clone() {
+            self.callbacks
+                .register(&job_id, owner, url, req.callback_secret.clone())
+                .await;
+        }
+        let run = Arc::new(RecurringRun {
+            job_id: job_id.clone(),
+            kind: schedule.kind(),
+            owner: owner.to_string(),
+            notifier: Arc::clone(&self.notifier),
+            outbound: self.outbound.clone(),
+            jobs: Arc::clone(&self.jobs),
+            events: Arc::clone(&self.events),
+            tags: req.tags.clone(),
+            notify_req: req.notify.clone(),
+            content_source: req.content_source.clone(),
+            condition: req.condition.clone(),
+            condition_state: AtomicU8::new(STATE_UNKNOWN),
+            cancelled: Arc::clone(&cancelled),
+            paused: Arc::clone(&paused),
+            current_count: AtomicU32::new(0),
+            max_count: req.max_count,
+            start_at: req.start_at,
+            end_at,
+        });
 
-        self.scheduler.add(job).await?;
+        let kind = schedule.kind();
+        let (cron, rrule, every, align_to) = match &schedule {
+            RecurringSchedule::Cron(expr, _) => (Some(expr.clone()), None, None, None),
+            RecurringSchedule::RRule(text, _) => (None, Some(text.clone()), None, None),
+            RecurringSchedule::Interval { every, anchor, .. } => {
+                (None, None, Some(every.clone()), Some(*anchor))
+            }
+        };
 
-        let jobs = Arc::clone(&self.jobs);
+        // 调度失败时撤销回调注册，避免残留不存在的任务
+        if let Err(e) = self.start_recurring(run, schedule, created_at).await {
+            self.callbacks.unregister(&job_id).await;
+            return Err(e);
+        }
+
+        let tags = req.tags.clone();
         let scheduled_job = ScheduledJob {
             id: job_id.clone(),
-            cron: Some(req.cron),
+            kind,
+            status: JobStatus::Active,
+            cron,
+            rrule,
+            every,
+            align_to,
             at: None,
             notify: req.notify,
-            created_at: Utc::now(),
-            max_count,
+            created_at,
+            max_count: req.max_count,
+            content_source: req.content_source,
+            condition: req.condition,
+            client_job_id: req.client_job_id,
+            tags: req.tags,
+            metadata: req.metadata,
+            owner: owner.to_string(),
+            start_at: req.start_at,
+            end_at,
+            callback_url: req.callback_url.clone(),
             cancelled,
+            paused,
         };
 
-        jobs.write().await.insert(job_id.clone(), scheduled_job);
-        info!("Added cron job {}, max_count: {:?}", job_id, max_count);
+        self.insert_job(scheduled_job, quota).await?;
+        info!("Added {:?} job {}, max_count: {:?}", kind, job_id, req.max_count);
+        self.events.publish(
+            EventKind::JobCreated,
+            Some(&job_id),
+            Some(owner),
+            &tags,
+            serde_json::json!({ "type": kind }),
+        );
 
         Ok(job_id)
     }
 
-    pub async fn add_one_time_job(&self, req: OneTimeRequest) -> anyhow::Result<String> {
-        let now = Utc::now();
-        
-        if req.at <= now {
-            return Err(anyhow::anyhow!(
-                "Scheduled time must be in the future. Now: {}, Scheduled: {}",
-                now, req.at
-            ));
+    /// 注册循环任务的触发：cron 交给调度器，RRULE 和固定间隔逐次注册下一次触发
+    async fn start_recurring(
+        &self,
+        run: Arc<RecurringRun>,
+        schedule: RecurringSchedule,
+        created_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let schedule = Arc::new(schedule);
+        if let RecurringSchedule::Cron(expr, _) = schedule.as_ref() {
+            let schedule = Arc::clone(&schedule);
+            let job = Job::new_async(expr.as_str(), move |uuid, l| {
+                let run = Arc::clone(&run);
+                let schedule = Arc::clone(&schedule);
+                Box::pin(async move {
+                    let next = schedule.next_after(Utc::now());
+                    if run.tick(next).await == TickOutcome::Finished {
+                        let _ = l.remove(&uuid).await;
+                    }
+                })
+            })?;
+            self.scheduler.add(job).await?;
+        } else {
+            let Some(first) = schedule.next_after(created_at) else {
+                return Err(anyhow::anyhow!("Schedule has no future occurrences"));
+            };
+            schedule_next_tick(self.scheduler.clone(), run, schedule, first).await?;
         }
+        Ok(())
+    }
+
+    pub async fn add_one_time_job(
+        &self,
+        mut req: OneTimeRequest,
+        owner: &str,
+        quota: Option<usize>,
+    ) -> anyhow::Result<String> {
+        let at = validate_one_time_request(&mut req)?;
+        self.check_device(req.notify.device.as_deref())?;
+        self.check_outbound(&req.outbound_urls()).await?;
+        self.check_callback(req.callback_url.as_deref(), req.callback_secret.as_deref())?;
+        let now = Utc::now();
 
         let job_id = Uuid::new_v4().to_string();
         let notifier = Arc::clone(&self.notifier);
         let notify_req = req.notify.clone();
         let job_id_clone = job_id.clone();
         let jobs = Arc::clone(&self.jobs);
+        let outbound = self.outbound.clone();
+        let content_source = req.content_source.clone();
+        let events = Arc::clone(&self.events);
+        let tags = req.tags.clone();
+        let job_owner = owner.to_string();
 
         // 创建取消标志
         let cancelled = Arc::new(AtomicBool::new(false));
         let cancelled_clone = Arc::clone(&cancelled);
 
         // 计算延迟时间
-        let duration = req.at.signed_duration_since(now);
+        let duration = at.signed_duration_since(now);
         let seconds = duration.num_seconds().max(0) as u64;
         
-        info!("Scheduling one-time job {} to run in {} seconds at {}", job_id, seconds, req.at);
+        info!("Scheduling one-time job {} to run in {} seconds at {}", job_id, seconds, at);
 
         // 创建定时任务：使用标准库的延迟执行，更可靠
         let job = Job::new_one_shot_async(
@@ -182,7 +976,12 @@ impl NotificationScheduler {
                 let job_id = job_id_clone.clone();
                 let jobs = Arc::clone(&jobs);
                 let cancelled = Arc::clone(&cancelled_clone);
-                
+                let outbound = outbound.clone();
+                let content_source = content_source.clone();
+                let events = Arc::clone(&events);
+                let tags = tags.clone();
+                let owner = job_owner.clone();
+
                 Box::pin(async move {
                     // 检查是否已被取消
                     if cancelled.load(Ordering::SeqCst) {
@@ -192,32 +991,82 @@ impl NotificationScheduler {
                     }
                     
                     info!("Executing one-time job {}", job_id);
-                    match notifier.send(&notify_req).await {
-                        Ok(_) => info!("One-time job {} executed successfully", job_id),
-                        Err(e) => error!("Failed to send one-time notification for job {}: {}", job_id, e),
+                    events.publish(EventKind::JobFired, Some(&job_id), Some(&owner), &tags, Value::Null);
+                    METRICS.inc(JOB_FIRES, &[("type", JobKind::Once.as_str())]);
+                    let notify_req = match &content_source {
+                        Some(source) => source.resolve(&outbound, &notify_req).await,
+                        None => notify_req,
+                    };
+                    match notifier.send_via(&notify_req, JobKind::Once.as_str()).await {
+                        Ok(resp) => {
+                            info!("One-time job {} executed successfully", job_id);
+                            publish_delivery(&events, &job_id, &owner, &tags, Ok(&resp));
+                        }
+                        Err(e) => {
+                            error!("Failed to send one-time notification for job {}: {}", job_id, e);
+                            publish_delivery(&events, &job_id, &owner, &tags, Err(&e));
+                        }
                     }
                     // 执行完成后从列表中移除
                     jobs.write().await.remove(&job_id);
                     info!("One-time job {} completed and removed", job_id);
+                    events.publish(
+                        EventKind::JobCompleted,
+                        Some(&job_id),
+                        Some(&owner),
+                        &tags,
+                        Value::Null,
+                    );
                 })
             }
         )?;
 
-        self.scheduler.add(job).await?;
+        if let Some(url) = req.callback_url.clone() {
+            self.callbacks
+                .register(&job_id, owner, url, req.callback_secret.clone())
+                .await;
+        }
+        if let Err(e) = self.scheduler.add(job).await {
+            self.callbacks.unregister(&job_id).await;
+            return Err(e.into());
+        }
 
-        let jobs = Arc::clone(&self.jobs);
         let scheduled_job = ScheduledJob {
             id: job_id.clone(),
+            kind: JobKind::Once,
+            status: JobStatus::Active,
             cron: None,
-            at: Some(req.at),
+            rrule: None,
+            every: None,
+            align_to: None,
+            at: Some(at),
             notify: req.notify,
             created_at: Utc::now(),
             max_count: Some(1), // 一次性任务默认执行1次
+            content_source: req.content_source,
+            condition: None,
+            client_job_id: req.client_job_id,
+            tags: req.tags,
+            metadata: req.metadata,
+            owner: owner.to_string(),
+            start_at: None,
+            end_at: None,
+            callback_url: req.callback_url.clone(),
             cancelled,
+            // 一次性任务不支持暂停
+            paused: Arc::new(AtomicBool::new(false)),
         };
 
-        jobs.write().await.insert(job_id.clone(), scheduled_job);
-        info!("Added one-time job {} at {} (in {} seconds)", job_id, req.at, seconds);
+        let tags = scheduled_job.tags.clone();
+        self.insert_job(scheduled_job, quota).await?;
+        info!("Added one-time job {} at {} (in {} seconds)", job_id, at, seconds);
+        self.events.publish(
+            EventKind::JobCreated,
+            Some(&job_id),
+            Some(owner),
+            &tags,
+            serde_json::json!({ "type": JobKind::Once, "at": at }),
+        );
 
         Ok(job_id)
     }
@@ -228,6 +1077,13 @@ impl NotificationScheduler {
         if let Some(job) = jobs.get(job_id) {
             // 设置取消标志，下次执行时会跳过
             job.cancelled.store(true, Ordering::SeqCst);
+            self.events.publish(
+                EventKind::JobCancelled,
+                Some(job_id),
+                Some(&job.owner),
+                &job.tags,
+                Value::Null,
+            );
             jobs.remove(job_id);
             info!("Job {} marked as cancelled and removed", job_id);
             Ok(())
@@ -236,11 +1092,219 @@ impl NotificationScheduler {
         }
     }
 
-    pub async fn list_jobs(&self) -> Vec<ScheduledJob> {
-        self.jobs.read().await.values().cloned().collect()
+    /// 静默撤销刚创建的任务：不发布取消事件，也不触发回调，用于批量创建回滚
+    pub async fn discard_job(&self, job_id: &str) {
+        self.callbacks.unregister(job_id).await;
+        if let Some(job) = self.jobs.write().await.remove(job_id) {
+            job.cancelled.store(true, Ordering::SeqCst);
+            info!("Job {} discarded", job_id);
+        }
+    }
+
+    /// 暂停循环任务，暂停期间的触发直接跳过且不计数
+    pub async fn pause_job(&self, job_id: &str) -> anyhow::Result<()> {
+        self.set_paused(job_id, true).await
+    }
+
+    pub async fn resume_job(&self, job_id: &str) -> anyhow::Result<()> {
+        self.set_paused(job_id, false).await
+    }
+
+    async fn set_paused(&self, job_id: &str, paused: bool) -> anyhow::Result<()> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs
+            .get_mut(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Job {} not found", job_id))?;
+
+        if job.kind == JobKind::Once {
+            return Err(anyhow::anyhow!("One-time job {} cannot be paused", job_id));
+        }
+
+        job.paused.store(paused, Ordering::SeqCst);
+        job.status = if paused {
+            JobStatus::Paused
+        } else {
+            JobStatus::Active
+        };
+        info!("Job {} {}", job_id, if paused { "paused" } else { "resumed" });
+        Ok(())
+    }
+
+    /// 返回匹配筛选条件的任务 ID
+    pub async fn find_job_ids(&self, filter: &JobFilter) -> Vec<String> {
+        let mut jobs: Vec<(String, String)> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| filter.matches(job))
+            .map(|job| (job_cursor(job), job.id.clone()))
+            .collect();
+        jobs.sort();
+        jobs.into_iter().map(|(_, id)| id).collect()
+    }
+
+    pub async fn query_jobs(&self, query: &JobQuery) -> JobPage {
+        let mut jobs: Vec<ScheduledJob> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| query.filter.matches(job))
+            .cloned()
+            .collect();
+
+        jobs.sort_by_key(job_cursor);
+        if query.sort == SortOrder::Desc {
+            jobs.reverse();
+        }
+
+        if let Some(cursor) = &query.cursor {
+            jobs.retain(|job| match query.sort {
+                SortOrder::Asc => job_cursor(job) > *cursor,
+                SortOrder::Desc => job_cursor(job) < *cursor,
+            });
+        }
+
+        let mut next_cursor = None;
+        if let Some(limit) = query.limit
+            && jobs.len() > limit
+        {
+            jobs.truncate(limit);
+            next_cursor = jobs.last().map(job_cursor);
+        }
+
+        JobPage { jobs, next_cursor }
     }
 
     pub async fn get_job(&self, job_id: &str) -> Option<ScheduledJob> {
         self.jobs.read().await.get(job_id).cloned()
     }
+
+    /// 创建前检查令牌再创建 adding 个任务后是否超出上限；最终以加入任务列表时的检查为准
+    pub async fn check_job_quota(
+        &self,
+        owner: &str,
+        max: Option<usize>,
+        adding: usize,
+    ) -> anyhow::Result<()> {
+        check_quota(&*self.jobs.read().await, owner, max, adding)
+    }
+
+    /// 在同一个写锁内检查配额并加入任务列表，避免并发创建超出上限；超出时取消已注册的调度和回调
+    async fn insert_job(&self, job: ScheduledJob, quota: Option<usize>) -> anyhow::Result<()> {
+        let mut jobs = self.jobs.write().await;
+        if let Err(e) = check_quota(&jobs, &job.owner, quota, 1) {
+            drop(jobs);
+            job.cancelled.store(true, Ordering::SeqCst);
+            self.callbacks.unregister(&job.id).await;
+            return Err(e);
+        }
+        jobs.insert(job.id.clone(), job);
+        Ok(())
+    }
+
+    /// 各类型处于 active 状态的任务数
+    pub async fn active_counts(&self) -> Vec<(JobKind, usize)> {
+        let jobs = self.jobs.read().await;
+        [JobKind::Cron, JobKind::Rrule, JobKind::Interval, JobKind::Once]
+            .into_iter()
+            .map(|kind| {
+                let count = jobs
+                    .values()
+                    .filter(|job| job.kind == kind && job.status == JobStatus::Active)
+                    .count();
+                (kind, count)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn created_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap()
+    }
+
+    fn request(bounds: Value) -> ScheduleRequest {
+        let mut body = serde_json::json!({"title": "t", "body": "b", "every": "1h"});
+        if let (Some(body), Value::Object(bounds)) = (body.as_object_mut(), bounds) {
+            body.extend(bounds);
+        }
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn end_of(bounds: Value) -> anyhow::Result<Option<DateTime<Utc>>> {
+        effective_end(&request(bounds), created_at())
+    }
+
+    #[test]
+    fn no_bounds_means_no_end() {
+        assert_eq!(end_of(serde_json::json!({})).unwrap(), None);
+        assert_eq!(
+            end_of(serde_json::json!({"start_at": "2026-10-20T00:00:00Z"})).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn expires_after_counts_from_creation() {
+        assert_eq!(
+            end_of(serde_json::json!({"expires_after": "1h30m"})).unwrap(),
+            Some(created_at() + chrono::Duration::minutes(90))
+        );
+    }
+
+    #[test]
+    fn earlier_of_end_at_and_expires_after_wins() {
+        let end_at = Utc.with_ymd_and_hms(2026, 10, 19, 10, 0, 0).unwrap();
+        assert_eq!(
+            end_of(serde_json::json!({"end_at": end_at, "expires_after": "2h"})).unwrap(),
+            Some(end_at)
+        );
+        assert_eq!(
+            end_of(serde_json::json!({"end_at": end_at, "expires_after": "30m"})).unwrap(),
+            Some(created_at() + chrono::Duration::minutes(30))
+        );
+    }
+
+    #[test]
+    fn rejects_end_in_the_past() {
+        let err = end_of(serde_json::json!({"end_at": created_at()})).unwrap_err();
+        assert!(err.to_string().contains("end_at must be in the future"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_start_at_after_end() {
+        let err = end_of(serde_json::json!({
+            "start_at": "2026-10-19T12:00:00Z",
+            "end_at": "2026-10-19T12:00:00Z",
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("start_at must be before end_at"), "{}", err);
+        let err = end_of(serde_json::json!({
+            "start_at": "2026-10-19T12:00:00Z",
+            "expires_after": "1h",
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("start_at must be before end_at"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_oversized_expires_after() {
+        for expires_after in ["9000000000000000s", "3651d"] {
+            let err = end_of(serde_json::json!({"expires_after": expires_after})).unwrap_err();
+            assert!(err.to_string().contains("too large"), "{}: {}", expires_after, err);
+        }
+        // 创建时间接近上限时相加溢出也只返回错误
+        let err = effective_end(
+            &request(serde_json::json!({"expires_after": "1d"})),
+            DateTime::<Utc>::MAX_UTC,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("too large"), "{}", err);
+    }
 }
@@ -0,0 +1,245 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::HeaderMap,
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::callback::{SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::tokens::{Principal, TokenRegistry};
+
+pub const KEY_ID_HEADER: &str = "x-bark-key-id";
+pub const NONCE_HEADER: &str = "x-bark-nonce";
+
+// 请求体上限，与 axum Json 提取器默认值一致
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+pub enum SignatureError {
+    TooLarge,
+    Invalid(&'static str),
+}
+
+/// 待签名字符串：方法、路径（含查询参数）、时间戳、nonce、请求体 SHA-256，以换行分隔
+pub fn string_to_sign(method: &str, path: &str, timestamp: &str, nonce: &str, body: &[u8]) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method,
+        path,
+        timestamp,
+        nonce,
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// 请求是否使用签名认证
+pub fn is_signed(request: &Request) -> bool {
+    request.headers().contains_key(KEY_ID_HEADER)
+}
+
+/// 校验 HMAC 请求签名：时间戳须在窗口内，同一密钥的 nonce 在窗口内只能使用一次
+pub struct SignatureVerifier {
+    window: Duration,
+    // "密钥 ID:nonce" -> 过期时间
+    nonces: Mutex<HashMap<String, Instant>>,
+}
+
+impl SignatureVerifier {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 校验签名，成功时返回重新组装的请求和对应的调用方
+    pub async fn verify(
+        &self,
+        tokens: &TokenRegistry,
+        request: Request,
+    ) -> Result<(Request, Principal), SignatureError> {
+        let (parts, body) = request.into_parts();
+        let header = |name| header_str(&parts.headers, name);
+        let (Some(key_id), Some(timestamp), Some(nonce), Some(signature)) = (
+            header(KEY_ID_HEADER),
+            header(TIMESTAMP_HEADER),
+            header(NONCE_HEADER),
+            header(SIGNATURE_HEADER),
+        ) else {
+            return Err(SignatureError::Invalid("missing signature headers"));
+        };
+
+        let ts: i64 = timestamp
+            .parse()
+            .map_err(|_| SignatureError::Invalid("invalid timestamp"))?;
+        if Utc::now().timestamp().abs_diff(ts) > self.window.as_secs() {
+            return Err(SignatureError::Invalid("timestamp outside the allowed window"));
+        }
+        if !(8..=128).contains(&nonce.len()) || !nonce.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(SignatureError::Invalid("nonce must be 8-128 printable characters"));
+        }
+        let signature = hex::decode(signature.strip_prefix("sha256=").unwrap_or(signature))
+            .map_err(|_| SignatureError::Invalid("invalid signature"))?;
+
+        let (key, principal) = tokens.signing_key(key_id).await.map_err(SignatureError::Invalid)?;
+        let bytes = to_bytes(body, MAX_BODY_BYTES)
+            .await
+            .map_err(|_| SignatureError::TooLarge)?;
+        let path = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(string_to_sign(parts.method.as_str(), path, timestamp, nonce, &bytes).as_bytes());
+        // verify_slice 为常量时间比较
+        mac.verify_slice(&signature)
+            .map_err(|_| SignatureError::Invalid("signature mismatch"))?;
+
+        // 签名通过后才记录 nonce，避免未认证的请求占满缓存
+        self.remember(format!("{}:{}", key_id, nonce))?;
+        Ok((Request::from_parts(parts, Body::from(bytes)), principal))
+    }
+
+    fn remember(&self, key: String) -> Result<(), SignatureError> {
+        let mut nonces = self.nonces.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        nonces.retain(|_, expires| *expires > now);
+        if nonces.contains_key(&key) {
+            return Err(SignatureError::Invalid("nonce already used"));
+        }
+        // 时间戳允许前后偏差 window，nonce 需保留两倍窗口
+        nonces.insert(key, now + self.window * 2);
+        Ok(())
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::{AuthKind, Scope, TokenConfig};
+
+    const KEY: &str = "sign-key";
+
+    fn registry() -> TokenRegistry {
+        let config = |name: &str, token: &str, auth| TokenConfig {
+            name: name.to_string(),
+            token: token.to_string(),
+            auth,
+            scopes: vec![Scope::Notify],
+            expires_at: None,
+            devices: Vec::new(),
+            rate_limit: None,
+            max_active_jobs: None,
+            allowed_networks: Vec::new(),
+            unix_uids: Vec::new(),
+        };
+        TokenRegistry::from_config(
+            "",
+            &[config("signer", KEY, AuthKind::Hmac), config("bearer", "tok", AuthKind::Bearer)],
+            "",
+        )
+        .unwrap()
+    }
+
+    fn sign(key: &str, timestamp: &str, nonce: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+        mac.update(string_to_sign("POST", "/notify?x=1", timestamp, nonce, body).as_bytes());
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn request(key_id: &str, timestamp: i64, nonce: &str, signature: &str, body: &str) -> Request {
+        Request::builder()
+            .method("POST")
+            .uri("/notify?x=1")
+            .header(KEY_ID_HEADER, key_id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(NONCE_HEADER, nonce)
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn signed(timestamp: i64, nonce: &str, body: &str) -> Request {
+        let signature = sign(KEY, &timestamp.to_string(), nonce, body.as_bytes());
+        request("signer", timestamp, nonce, &signature, body)
+    }
+
+    async fn verify(verifier: &SignatureVerifier, request: Request) -> Result<String, &'static str> {
+        match verifier.verify(&registry(), request).await {
+            Ok((request, principal)) => {
+                let body = to_bytes(request.into_body(), MAX_BODY_BYTES).await.unwrap();
+                assert!(!body.is_empty(), "body must be restored after verification");
+                Ok(principal.id)
+            }
+            Err(SignatureError::Invalid(reason)) => Err(reason),
+            Err(SignatureError::TooLarge) => Err("too large"),
+        }
+    }
+
+    #[test]
+    fn string_to_sign_hashes_the_body() {
+        assert_eq!(
+            string_to_sign("POST", "/notify", "1700000000", "nonce-123", b""),
+            "POST\n/notify\n1700000000\nnonce-123\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[tokio::test]
+    async fn accepts_a_valid_signature() {
+        let verifier = SignatureVerifier::new(Duration::from_secs(300));
+        let now = Utc::now().timestamp();
+        assert_eq!(verify(&verifier, signed(now, "nonce-0001", "{}")).await, Ok("signer".into()));
+    }
+
+    #[tokio::test]
+    async fn rejects_a_tampered_body() {
+        let verifier = SignatureVerifier::new(Duration::from_secs(300));
+        let now = Utc::now().timestamp();
+        let signature = sign(KEY, &now.to_string(), "nonce-0001", b"{\"title\":\"a\"}");
+        let tampered = request("signer", now, "nonce-0001", &signature, "{\"title\":\"b\"}");
+        assert_eq!(verify(&verifier, tampered).await, Err("signature mismatch"));
+    }
+
+    #[tokio::test]
+    async fn rejects_timestamps_outside_the_window() {
+        let verifier = SignatureVerifier::new(Duration::from_secs(300));
+        let now = Utc::now().timestamp();
+        let reason = Err("timestamp outside the allowed window");
+        assert_eq!(verify(&verifier, signed(now - 301, "nonce-0001", "{}")).await, reason);
+        assert_eq!(verify(&verifier, signed(now + 301, "nonce-0002", "{}")).await, reason);
+        assert!(verify(&verifier, signed(now - 290, "nonce-0003", "{}")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_a_replayed_nonce() {
+        let verifier = SignatureVerifier::new(Duration::from_secs(300));
+        let now = Utc::now().timestamp();
+        assert!(verify(&verifier, signed(now, "nonce-0001", "{}")).await.is_ok());
+        assert_eq!(
+            verify(&verifier, signed(now, "nonce-0001", "{}")).await,
+            Err("nonce already used")
+        );
+        // 签名不通过的请求不占用 nonce
+        let bad = request("signer", now, "nonce-0002", "sha256=00", "{}");
+        assert_eq!(verify(&verifier, bad).await, Err("signature mismatch"));
+        assert!(verify(&verifier, signed(now, "nonce-0002", "{}")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_and_bearer_keys() {
+        let verifier = SignatureVerifier::new(Duration::from_secs(300));
+        let now = Utc::now().timestamp();
+        let signature = sign(KEY, &now.to_string(), "nonce-0001", b"{}");
+        let unknown = request("nobody", now, "nonce-0001", &signature, "{}");
+        assert_eq!(verify(&verifier, unknown).await, Err("unknown signing key"));
+        let bearer = request("bearer", now, "nonce-0001", &signature, "{}");
+        assert_eq!(verify(&verifier, bearer).await, Err("token does not use request signing"));
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::notify::NotifyRequest;
+
+/// 通知模板：NotifyRequest 的部分预设，请求中未填写的字段使用模板值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyTemplate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_copy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub badge: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub template: NotifyTemplate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedTemplate {
+    pub name: String,
+    #[serde(flatten)]
+    pub template: NotifyTemplate,
+}
+
+impl NotifyTemplate {
+    /// 用模板补全请求：请求中已设置的字段优先
+    pub fn apply(&self, req: &mut NotifyRequest) {
+        if req.title.is_empty()
+            && let Some(title) = &self.title
+        {
+            req.title = title.clone();
+        }
+        if req.body.is_empty()
+            && let Some(body) = &self.body
+        {
+            req.body = body.clone();
+        }
+        fill(&mut req.sound, &self.sound);
+        fill(&mut req.group, &self.group);
+        fill(&mut req.level, &self.level);
+        fill(&mut req.icon, &self.icon);
+        fill(&mut req.url, &self.url);
+        fill(&mut req.copy, &self.copy);
+        fill(&mut req.auto_copy, &self.auto_copy);
+        fill(&mut req.badge, &self.badge);
+    }
+}
+
+fn fill<T: Clone>(field: &mut Option<T>, default: &Option<T>) {
+    if field.is_none() {
+        field.clone_from(default);
+    }
+}
+
+pub struct TemplateStore {
+    templates: RwLock<HashMap<String, NotifyTemplate>>,
+}
+
+impl TemplateStore {
+    /// 创建模板存储，预置 alert / reminder / report 三个常用模板
+    pub fn new() -> Self {
+        let mut templates = HashMap::new();
+        templates.insert(
+            "alert".to_string(),
+            NotifyTemplate {
+                sound: Some("alarm".to_string()),
+                group: Some("alert".to_string()),
+                level: Some("timeSensitive".to_string()),
+                ..Default::default()
+            },
+        );
+        templates.insert(
+            "reminder".to_string(),
+            NotifyTemplate {
+                sound: Some("bell".to_string()),
+                group: Some("reminder".to_string()),
+                level: Some("active".to_string()),
+                ..Default::default()
+            },
+        );
+        templates.insert(
+            "report".to_string(),
+            NotifyTemplate {
+                group: Some("report".to_string()),
+                level: Some("passive".to_string()),
+                ..Default::default()
+            },
+        );
+
+        Self {
+            templates: RwLock::new(templates),
+        }
+    }
+
+    pub async fn create(&self, req: TemplateRequest) -> anyhow::Result<()> {
+        validate_name(&req.name)?;
+        let mut templates = self.templates.write().await;
+        if templates.contains_key(&req.name) {
+            return Err(anyhow::anyhow!("Template {} already exists", req.name));
+        }
+        info!("Added template {}", req.name);
+        templates.insert(req.name, req.template);
+        Ok(())
+    }
+
+    pub async fn update(&self, name: &str, template: NotifyTemplate) -> anyhow::Result<()> {
+        let mut templates = self.templates.write().await;
+        match templates.get_mut(name) {
+            Some(existing) => {
+                *existing = template;
+                info!("Updated template {}", name);
+                Ok(())
+            }
+            None => Err(anyhow::anyhow!("Template {} not found", name)),
+        }
+    }
+
+    pub async fn remove(&self, name: &str) -> anyhow::Result<()> {
+        match self.templates.write().await.remove(name) {
+            Some(_) => {
+                info!("Removed template {}", name);
+                Ok(())
+            }
+            None => Err(anyhow::anyhow!("Template {} not found", name)),
+        }
+    }
+
+    pub async fn get(&self, name: &str) -> Option<NotifyTemplate> {
+        self.templates.read().await.get(name).cloned()
+    }
+
+    pub async fn list(&self) -> Vec<NamedTemplate> {
+        let mut list: Vec<NamedTemplate> = self
+            .templates
+            .read()
+            .await
+            .iter()
+            .map(|(name, template)| NamedTemplate {
+                name: name.clone(),
+                template: template.clone(),
+            })
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    /// 解析请求中引用的模板并合并，返回可直接发送的请求
+    pub async fn resolve(&self, mut req: NotifyRequest) -> anyhow::Result<NotifyRequest> {
+        if let Some(name) = req.template.take() {
+            let template = self
+                .get(&name)
+                .await
+                .ok_or_else(|| anyhow::anyhow!("Template {} not found", name))?;
+            template.apply(&mut req);
+        }
+
+        if req.title.is_empty() && req.body.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Notification must have a title or body (directly or via template)"
+            ));
+        }
+        Ok(req)
+    }
+}
+
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid template name: use 1-64 letters, digits, '-' or '_'"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+// 检查证书文件是否变化的间隔
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// HTTPS 配置：PEM 格式的证书链和私钥；设置 client_ca 时要求客户端出示由该 CA 签发的证书（mTLS）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    #[serde(default)]
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    pub fn mutual(&self) -> bool {
+        self.client_ca.is_some()
+    }
+
+    fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        [&self.cert, &self.key].into_iter().chain(self.client_ca.as_ref())
+    }
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// 读取证书和私钥，构建 rustls 服务端配置
+pub fn server_config(config: &TlsConfig) -> anyhow::Result<Arc<ServerConfig>> {
+    let certs = load_certs(&config.cert)?;
+    let key = load_key(&config.key)?;
+    let builder = ServerConfig::builder_with_provider(provider()).with_safe_default_protocol_versions()?;
+    let builder = match &config.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| anyhow::anyhow!("Invalid CA certificate in {}: {}", path.display(), e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider())
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid client_ca {}: {}", path.display(), e))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| anyhow::anyhow!("Failed to load certificate {}: {}", config.cert.display(), e))?;
+    server.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(server))
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("Failed to read certificates from {}: {}", path.display(), e))?;
+    if certs.is_empty() {
+        return Err(anyhow::anyhow!("No PEM certificates found in {}", path.display()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> anyhow::Result<PrivateKeyDer<'static>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| anyhow::anyhow!("Failed to read private key from {}: {}", path.display(), e))?
+        .ok_or_else(|| anyhow::anyhow!("No PEM private key found in {}", path.display()))
+}
+
+fn modified(config: &TlsConfig) -> Vec<Option<SystemTime>> {
+    config
+        .files()
+        .map(|path| path.metadata().and_then(|meta| meta.modified()).ok())
+        .collect()
+}
+
+/// 定期检查证书文件的修改时间，变化后重新加载，新连接使用新证书；加载失败时保留原证书
+pub fn watch(config: TlsConfig, rustls: RustlsConfig) {
+    tokio::spawn(async move {
+        let mut last = modified(&config);
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let current = modified(&config);
+            if current == last {
+                continue;
+            }
+            // 文件可能仍在写入，失败后等下一次修改再重试
+            last = current;
+            match server_config(&config) {
+                Ok(server) => {
+                    rustls.reload_from_config(server);
+                    info!("Reloaded TLS certificate from {}", config.cert.display());
+                }
+                Err(e) => warn!("Failed to reload TLS certificate, keeping the current one: {}", e),
+            }
+        }
+    });
+}
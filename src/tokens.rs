@@ -0,0 +1,982 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::network::{self, Network};
+use crate::ratelimit::RateLimit;
+
+/// 令牌的权限范围；admin 包含全部权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "notify")]
+    Notify,
+    #[serde(rename = "schedule")]
+    Schedule,
+    #[serde(rename = "jobs:read")]
+    JobsRead,
+    #[serde(rename = "jobs:write")]
+    JobsWrite,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Notify => "notify",
+            Scope::Schedule => "schedule",
+            Scope::JobsRead => "jobs:read",
+            Scope::JobsWrite => "jobs:write",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+/// 令牌的认证方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthKind {
+    // 通过 Authorization: Bearer 直接出示令牌
+    #[default]
+    Bearer,
+    // 令牌作为 HMAC 密钥对请求签名，令牌本身不在请求中传输
+    Hmac,
+}
+
+/// 配置文件中定义的令牌
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenConfig {
+    pub name: String,
+    // 只通过 unix_uids 认证时可以留空
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub auth: AuthKind,
+    #[serde(default)]
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    // 允许推送的设备名称，为空表示全部设备
+    #[serde(default)]
+    pub devices: Vec<String>,
+    // 覆盖 [rate_limit] 中的 per_token 和 max_active_jobs
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub max_active_jobs: Option<usize>,
+    // 只允许从这些网段使用该令牌，为空表示不限制
+    #[serde(default)]
+    pub allowed_networks: Vec<Network>,
+    // 通过 Unix socket 连接且对端进程属于这些 UID 时，无需出示令牌即以该令牌认证
+    #[serde(default)]
+    pub unix_uids: Vec<u32>,
+}
+
+/// 通过 /admin/tokens 创建令牌的请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    pub auth: AuthKind,
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub devices: Vec<String>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub max_active_jobs: Option<usize>,
+    #[serde(default)]
+    pub allowed_networks: Vec<Network>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenSource {
+    // 配置文件中定义，只能通过修改配置变更
+    Config,
+    Api,
+}
+
+/// 已登记的令牌，只保存令牌的哈希
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub auth: AuthKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_active_jobs: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_networks: Vec<Network>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unix_uids: Vec<u32>,
+    pub source: TokenSource,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotated_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    secret: Secret,
+}
+
+/// 令牌的保存形式
+#[derive(Debug, Clone)]
+enum Secret {
+    // 明文配置或接口生成的令牌，保存 SHA-256
+    Digest([u8; 32]),
+    // 配置中以 $argon2 开头的 PHC 格式哈希
+    Argon2(String),
+    // 配置中以 $2a$ / $2b$ / $2y$ 开头的 bcrypt 哈希
+    Bcrypt(String),
+    // HMAC 签名密钥，校验签名需要明文
+    Hmac(String),
+    // 未设置令牌值，只能通过 Unix socket 对端 UID 认证
+    None,
+}
+
+impl Secret {
+    fn from_config(value: &str, auth: AuthKind) -> anyhow::Result<Self> {
+        if value.is_empty() {
+            if auth == AuthKind::Hmac {
+                return Err(anyhow::anyhow!("HMAC tokens need a secret"));
+            }
+            return Ok(Secret::None);
+        }
+        let hashed = value.starts_with("$argon2")
+            || ["$2a$", "$2b$", "$2y$"].iter().any(|p| value.starts_with(p));
+        if auth == AuthKind::Hmac {
+            if hashed {
+                return Err(anyhow::anyhow!("HMAC tokens need the plain secret, not a hash"));
+            }
+            return Ok(Secret::Hmac(value.to_string()));
+        }
+        if value.starts_with("$argon2") {
+            PasswordHash::new(value).map_err(|e| anyhow::anyhow!("Invalid argon2 hash: {}", e))?;
+            Ok(Secret::Argon2(value.to_string()))
+        } else if ["$2a$", "$2b$", "$2y$"].iter().any(|p| value.starts_with(p)) {
+            Ok(Secret::Bcrypt(value.to_string()))
+        } else {
+            Ok(Secret::Digest(digest(value)))
+        }
+    }
+
+    /// 生成新令牌，返回明文和保存形式
+    fn generate(auth: AuthKind) -> (String, Self) {
+        let token = generate_token();
+        let secret = match auth {
+            AuthKind::Bearer => Secret::Digest(digest(&token)),
+            AuthKind::Hmac => Secret::Hmac(token.clone()),
+        };
+        (token, secret)
+    }
+
+    /// argon2 / bcrypt 校验较慢，需在阻塞线程中执行
+    fn verify_slow(&self, token: &str) -> bool {
+        match self {
+            Secret::Digest(_) | Secret::Hmac(_) | Secret::None => false,
+            Secret::Argon2(hash) => PasswordHash::new(hash)
+                .is_ok_and(|hash| Argon2::default().verify_password(token.as_bytes(), &hash).is_ok()),
+            Secret::Bcrypt(hash) => bcrypt::verify(token, hash).unwrap_or(false),
+        }
+    }
+}
+
+/// 令牌文件中的一条记录：Bearer 令牌保存 SHA-256，HMAC 令牌需保存签名密钥
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredToken {
+    id: String,
+    name: String,
+    scopes: Vec<Scope>,
+    auth: AuthKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    devices: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rate_limit: Option<RateLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_active_jobs: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowed_networks: Vec<Network>,
+    created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rotated_at: Option<DateTime<Utc>>,
+    // Bearer 为十六进制 SHA-256，HMAC 为密钥明文
+    secret: String,
+}
+
+impl StoredToken {
+    fn from_token(token: &ApiToken) -> Option<Self> {
+        let secret = match &token.secret {
+            Secret::Digest(digest) => hex::encode(digest),
+            Secret::Hmac(key) => key.clone(),
+            // 接口创建的令牌只会是以上两种
+            Secret::Argon2(_) | Secret::Bcrypt(_) | Secret::None => return None,
+        };
+        Some(Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            auth: token.auth,
+            expires_at: token.expires_at,
+            devices: token.devices.clone(),
+            rate_limit: token.rate_limit,
+            max_active_jobs: token.max_active_jobs,
+            allowed_networks: token.allowed_networks.clone(),
+            created_at: token.created_at,
+            rotated_at: token.rotated_at,
+            secret,
+        })
+    }
+
+    fn into_token(self) -> anyhow::Result<ApiToken> {
+        let secret = match self.auth {
+            AuthKind::Bearer => {
+                let digest = hex::decode(&self.secret)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .ok_or_else(|| anyhow::anyhow!("Token {}: invalid secret digest", self.name))?;
+                Secret::Digest(digest)
+            }
+            AuthKind::Hmac => Secret::Hmac(self.secret),
+        };
+        Ok(ApiToken {
+            id: self.id,
+            name: self.name,
+            scopes: self.scopes,
+            auth: self.auth,
+            expires_at: self.expires_at,
+            devices: self.devices,
+            rate_limit: self.rate_limit,
+            max_active_jobs: self.max_active_jobs,
+            allowed_networks: self.allowed_networks,
+            unix_uids: Vec::new(),
+            source: TokenSource::Api,
+            created_at: self.created_at,
+            rotated_at: self.rotated_at,
+            secret,
+        })
+    }
+}
+
+/// 新创建或轮换后的令牌，明文只在此时返回一次
+#[derive(Debug, Serialize)]
+pub struct IssuedToken {
+    #[serde(flatten)]
+    pub info: ApiToken,
+    pub token: String,
+}
+
+/// 通过认证的调用方，由 auth_middleware 放入请求扩展
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub devices: Vec<String>,
+    pub rate_limit: Option<RateLimit>,
+    pub max_active_jobs: Option<usize>,
+    pub allowed_networks: Vec<Network>,
+}
+
+impl Principal {
+    /// 未配置任何令牌时的调用方，拥有全部权限
+    pub fn anonymous() -> Self {
+        Self {
+            id: "anonymous".to_string(),
+            name: "anonymous".to_string(),
+            scopes: vec![Scope::Admin],
+            devices: Vec::new(),
+            rate_limit: None,
+            max_active_jobs: None,
+            allowed_networks: Vec::new(),
+        }
+    }
+
+    pub fn has(&self, scope: Scope) -> bool {
+        self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+    }
+
+    /// 是否可以查看、修改该令牌创建的任务
+    pub fn owns(&self, owner: &str) -> bool {
+        self.has(Scope::Admin) || self.id == owner
+    }
+
+    /// 查询任务时限定的所属令牌，admin 可查看全部
+    pub fn owner_filter(&self) -> Option<String> {
+        (!self.has(Scope::Admin)).then(|| self.id.clone())
+    }
+
+    /// 令牌限制了来源网段时，检查客户端 IP 是否在其中
+    pub fn allows_ip(&self, ip: Option<IpAddr>) -> bool {
+        self.allowed_networks.is_empty()
+            || ip.is_some_and(|ip| network::contains(&self.allowed_networks, ip))
+    }
+
+    /// 检查是否允许向设备推送，device 为空表示默认设备
+    pub fn check_device(&self, device: Option<&str>) -> anyhow::Result<()> {
+        let device = device.unwrap_or(crate::notify::DEFAULT_DEVICE);
+        if self.devices.is_empty() || self.devices.iter().any(|d| d == device) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Token {} is not allowed to send to device {}",
+                self.name,
+                device
+            ))
+        }
+    }
+}
+
+fn digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// 生成随机令牌：bk_ 前缀加 64 位十六进制
+fn generate_token() -> String {
+    format!(
+        "bk_{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid token name: use 1-64 letters, digits, '-' or '_'"
+        ));
+    }
+    Ok(())
+}
+
+/// 令牌登记表：配置文件中的令牌和通过接口创建的令牌，按令牌哈希认证调用方
+pub struct TokenRegistry {
+    tokens: RwLock<HashMap<String, ApiToken>>,
+    // 已校验通过的 argon2 / bcrypt 令牌：SHA-256 -> 令牌 ID，避免每次请求都做慢哈希
+    verified: RwLock<HashMap<[u8; 32], String>>,
+    // 接口创建的令牌保存位置，None 表示只保存在内存中
+    store: Option<PathBuf>,
+}
+
+impl TokenRegistry {
+    /// 从配置加载令牌；设置了 password 时作为名为 default 的 admin 令牌。
+    /// store 不为空时再加载其中保存的接口令牌
+    pub fn from_config(
+        password: &str,
+        configured: &[TokenConfig],
+        store: &str,
+    ) -> anyhow::Result<Self> {
+        let mut tokens = HashMap::new();
+        let now = Utc::now();
+        let legacy = (!password.is_empty()).then(|| TokenConfig {
+            name: "default".to_string(),
+            token: password.to_string(),
+            auth: AuthKind::Bearer,
+            scopes: vec![Scope::Admin],
+            expires_at: None,
+            devices: Vec::new(),
+            rate_limit: None,
+            max_active_jobs: None,
+            allowed_networks: Vec::new(),
+            unix_uids: Vec::new(),
+        });
+
+        for config in legacy.iter().chain(configured) {
+            if config.name.is_empty() || (config.token.is_empty() && config.unix_uids.is_empty()) {
+                return Err(anyhow::anyhow!("Each token needs a name and a token or unix_uids"));
+            }
+            if let Some(uid) = config.unix_uids.iter().find(|uid| {
+                tokens.values().any(|token: &ApiToken| token.unix_uids.contains(uid))
+            }) {
+                return Err(anyhow::anyhow!("Token {}: UID {} is used by another token", config.name, uid));
+            }
+            if config.scopes.is_empty() {
+                return Err(anyhow::anyhow!("Token {} has no scopes", config.name));
+            }
+            if let Some(limit) = &config.rate_limit {
+                limit
+                    .validate()
+                    .map_err(|e| anyhow::anyhow!("Token {} rate_limit: {}", config.name, e))?;
+            }
+            // 配置令牌以名称作为 ID，重启后保持不变
+            if tokens.contains_key(&config.name) {
+                return Err(anyhow::anyhow!("Duplicate token name: {}", config.name));
+            }
+            let secret = Secret::from_config(&config.token, config.auth)
+                .map_err(|e| anyhow::anyhow!("Token {}: {}", config.name, e))?;
+            if let Secret::Digest(new) = &secret
+                && tokens.values().any(|token: &ApiToken| {
+                    matches!(&token.secret, Secret::Digest(existing) if existing == new)
+                })
+            {
+                return Err(anyhow::anyhow!("Token {} reuses another token's value", config.name));
+            }
+            tokens.insert(
+                config.name.clone(),
+                ApiToken {
+                    id: config.name.clone(),
+                    name: config.name.clone(),
+                    scopes: config.scopes.clone(),
+                    auth: config.auth,
+                    expires_at: config.expires_at,
+                    devices: config.devices.clone(),
+                    rate_limit: config.rate_limit,
+                    max_active_jobs: config.max_active_jobs,
+                    allowed_networks: config.allowed_networks.clone(),
+                    unix_uids: config.unix_uids.clone(),
+                    source: TokenSource::Config,
+                    created_at: now,
+                    rotated_at: None,
+                    secret,
+                },
+            );
+        }
+
+        let store = (!store.is_empty()).then(|| PathBuf::from(store));
+        if let Some(path) = &store {
+            load_store(path, &mut tokens)?;
+        }
+
+        Ok(Self {
+            tokens: RwLock::new(tokens),
+            verified: RwLock::new(HashMap::new()),
+            store,
+        })
+    }
+
+    /// 将接口创建的令牌写入 store：先写临时文件再替换，文件仅所有者可读写
+    async fn save(&self, tokens: &HashMap<String, ApiToken>) -> anyhow::Result<()> {
+        let Some(path) = &self.store else {
+            return Ok(());
+        };
+        let mut stored: Vec<StoredToken> = tokens
+            .values()
+            .filter(|token| token.source == TokenSource::Api)
+            .filter_map(StoredToken::from_token)
+            .collect();
+        stored.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.name.cmp(&b.name)));
+        let data = serde_json::to_vec_pretty(&stored)?;
+
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let write = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(0o600)
+                .open(&tmp)
+                .await?;
+            // mode 只在新建文件时生效，残留的临时文件也要收紧权限
+            file.set_permissions(std::fs::Permissions::from_mode(0o600)).await?;
+            file.write_all(&data).await?;
+            file.sync_all().await?;
+            tokio::fs::rename(&tmp, path).await
+        };
+        write
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to save tokens to {}: {}", path.display(), e))
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.tokens.read().await.is_empty()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.tokens.read().await.len()
+    }
+
+    pub async fn list(&self) -> Vec<ApiToken> {
+        let mut tokens: Vec<ApiToken> = self.tokens.read().await.values().cloned().collect();
+        tokens.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.name.cmp(&b.name)));
+        tokens
+    }
+
+    /// 创建令牌，立即生效
+    pub async fn create(&self, req: TokenRequest) -> anyhow::Result<IssuedToken> {
+        validate_name(&req.name)?;
+        if req.scopes.is_empty() {
+            return Err(anyhow::anyhow!("At least one scope is required"));
+        }
+        if let Some(limit) = &req.rate_limit {
+            limit.validate().map_err(|e| anyhow::anyhow!("rate_limit: {}", e))?;
+        }
+        if req.expires_at.is_some_and(|at| at <= Utc::now()) {
+            return Err(anyhow::anyhow!("expires_at must be in the future"));
+        }
+
+        let mut tokens = self.tokens.write().await;
+        if tokens.values().any(|token| token.name == req.name) {
+            return Err(anyhow::anyhow!("Token {} already exists", req.name));
+        }
+        let (value, secret) = Secret::generate(req.auth);
+        let token = ApiToken {
+            id: Uuid::new_v4().to_string(),
+            name: req.name,
+            scopes: req.scopes,
+            auth: req.auth,
+            expires_at: req.expires_at,
+            devices: req.devices,
+            rate_limit: req.rate_limit,
+            max_active_jobs: req.max_active_jobs,
+            allowed_networks: req.allowed_networks,
+            unix_uids: Vec::new(),
+            source: TokenSource::Api,
+            created_at: Utc::now(),
+            rotated_at: None,
+            secret,
+        };
+        tokens.insert(token.id.clone(), token.clone());
+        if let Err(e) = self.save(&tokens).await {
+            tokens.remove(&token.id);
+            return Err(e);
+        }
+        Ok(IssuedToken {
+            info: token,
+            token: value,
+        })
+    }
+
+    /// 删除令牌，立即失效；不能删除最后一个 admin 令牌
+    pub async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        let mut tokens = self.tokens.write().await;
+        let token = tokens.get(id).ok_or_else(|| not_found(id))?;
+        check_editable(token)?;
+        if token.scopes.contains(&Scope::Admin)
+            && !tokens
+                .values()
+                .any(|other| other.id != id && other.scopes.contains(&Scope::Admin))
+        {
+            return Err(anyhow::anyhow!("Cannot delete the last admin token"));
+        }
+        let Some(removed) = tokens.remove(id) else {
+            return Err(not_found(id));
+        };
+        if let Err(e) = self.save(&tokens).await {
+            tokens.insert(removed.id.clone(), removed);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// 轮换令牌：生成新的明文，旧令牌立即失效，ID、权限不变
+    pub async fn rotate(&self, id: &str) -> anyhow::Result<IssuedToken> {
+        let mut tokens = self.tokens.write().await;
+        let token = tokens.get_mut(id).ok_or_else(|| not_found(id))?;
+        check_editable(token)?;
+        let (value, secret) = Secret::generate(token.auth);
+        let previous = (
+            std::mem::replace(&mut token.secret, secret),
+            token.rotated_at.replace(Utc::now()),
+        );
+        let info = token.clone();
+        if let Err(e) = self.save(&tokens).await {
+            if let Some(token) = tokens.get_mut(id) {
+                (token.secret, token.rotated_at) = previous;
+            }
+            return Err(e);
+        }
+        Ok(IssuedToken { info, token: value })
+    }
+
+    /// 校验令牌，返回对应的调用方；令牌无效或已过期时返回错误信息
+    pub async fn authenticate(&self, token: &str) -> Result<Principal, &'static str> {
+        let digest = digest(token);
+        let id = match self.match_digest(&digest).await {
+            Some(id) => id,
+            None => self.match_slow(token, digest).await.ok_or("invalid or missing token")?,
+        };
+
+        let tokens = self.tokens.read().await;
+        tokens.get(&id).ok_or("invalid or missing token")?.principal()
+    }
+
+    /// 按 Unix socket 对端进程的 UID 认证
+    pub async fn authenticate_uid(&self, uid: u32) -> Result<Principal, &'static str> {
+        let tokens = self.tokens.read().await;
+        tokens
+            .values()
+            .find(|token| token.unix_uids.contains(&uid))
+            .ok_or("invalid or missing token")?
+            .principal()
+    }
+
+    /// 查找 HMAC 令牌的签名密钥；key_id 为令牌 ID（配置令牌即名称）
+    pub async fn signing_key(&self, key_id: &str) -> Result<(String, Principal), &'static str> {
+        let tokens = self.tokens.read().await;
+        let token = tokens.get(key_id).ok_or("unknown signing key")?;
+        let Secret::Hmac(key) = &token.secret else {
+            return Err("token does not use request signing");
+        };
+        Ok((key.clone(), token.principal()?))
+    }
+
+    /// 与全部 SHA-256 令牌做常量时间比较，不因提前命中而返回
+    async fn match_digest(&self, digest: &[u8; 32]) -> Option<String> {
+        let tokens = self.tokens.read().await;
+        let mut found = None;
+        for token in tokens.values() {
+            if let Secret::Digest(expected) = &token.secret
+                && bool::from(expected.ct_eq(digest))
+            {
+                found = Some(token.id.clone());
+            }
+        }
+        if found.is_some() {
+            return found;
+        }
+        self.verified.read().await.get(digest).cloned()
+    }
+
+    /// 逐个校验 argon2 / bcrypt 令牌，成功后缓存
+    async fn match_slow(&self, token: &str, digest: [u8; 32]) -> Option<String> {
+        let candidates: Vec<(String, Secret)> = self
+            .tokens
+            .read()
+            .await
+            .values()
+            .filter(|t| matches!(t.secret, Secret::Argon2(_) | Secret::Bcrypt(_)))
+            .map(|t| (t.id.clone(), t.secret.clone()))
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let token = token.to_string();
+        let id = tokio::task::spawn_blocking(move || {
+            candidates
+                .into_iter()
+                .find(|(_, secret)| secret.verify_slow(&token))
+                .map(|(id, _)| id)
+        })
+        .await
+        .ok()
+        .flatten()?;
+        self.verified.write().await.insert(digest, id.clone());
+        Some(id)
+    }
+}
+
+impl ApiToken {
+    fn principal(&self) -> Result<Principal, &'static str> {
+        if self.expires_at.is_some_and(|at| at <= Utc::now()) {
+            return Err("token expired");
+        }
+        Ok(Principal {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            scopes: self.scopes.clone(),
+            devices: self.devices.clone(),
+            rate_limit: self.rate_limit,
+            max_active_jobs: self.max_active_jobs,
+            allowed_networks: self.allowed_networks.clone(),
+        })
+    }
+}
+
+/// 加载 store 中的接口令牌；文件不存在时视为没有
+fn load_store(path: &Path, tokens: &mut HashMap<String, ApiToken>) -> anyhow::Result<()> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(anyhow::anyhow!("Failed to read {}: {}", path.display(), e)),
+    };
+    let stored: Vec<StoredToken> = serde_json::from_slice(&data)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    for record in stored {
+        let token = record
+            .into_token()
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        if tokens.contains_key(&token.id) || tokens.values().any(|t| t.name == token.name) {
+            return Err(anyhow::anyhow!(
+                "Token {} in {} conflicts with a token in the config",
+                token.name,
+                path.display()
+            ));
+        }
+        tokens.insert(token.id.clone(), token);
+    }
+    Ok(())
+}
+
+fn not_found(id: &str) -> anyhow::Error {
+    anyhow::anyhow!("Token {} not found", id)
+}
+
+/// 只有通过接口创建的令牌可以删除、轮换，配置文件中的令牌需修改配置
+fn check_editable(token: &ApiToken) -> anyhow::Result<()> {
+    if token.source == TokenSource::Config {
+        return Err(anyhow::anyhow!(
+            "Token {} is defined in the config file, change it there",
+            token.name
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+
+    fn config(name: &str, token: &str) -> TokenConfig {
+        TokenConfig {
+            name: name.to_string(),
+            token: token.to_string(),
+            auth: AuthKind::Bearer,
+            scopes: vec![Scope::Notify],
+            expires_at: None,
+            devices: Vec::new(),
+            rate_limit: None,
+            max_active_jobs: None,
+            allowed_networks: Vec::new(),
+            unix_uids: Vec::new(),
+        }
+    }
+
+    fn registry(configured: &[TokenConfig]) -> TokenRegistry {
+        TokenRegistry::from_config("", configured, "").unwrap()
+    }
+
+    fn argon2_hash(token: &str) -> String {
+        let salt = SaltString::encode_b64(b"agent-bark-salt").unwrap();
+        Argon2::default()
+            .hash_password(token.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn secret_from_config_detects_hashes() {
+        assert!(matches!(
+            Secret::from_config("plain", AuthKind::Bearer).unwrap(),
+            Secret::Digest(d) if d == digest("plain")
+        ));
+        assert!(matches!(
+            Secret::from_config(&argon2_hash("pw"), AuthKind::Bearer).unwrap(),
+            Secret::Argon2(_)
+        ));
+        assert!(Secret::from_config("$argon2id$v=19$m=x$", AuthKind::Bearer).is_err());
+        assert!(matches!(
+            Secret::from_config(&bcrypt::hash("pw", 4).unwrap(), AuthKind::Bearer).unwrap(),
+            Secret::Bcrypt(_)
+        ));
+        assert!(matches!(Secret::from_config("", AuthKind::Bearer).unwrap(), Secret::None));
+    }
+
+    #[test]
+    fn hmac_secrets_must_be_plain() {
+        assert!(matches!(
+            Secret::from_config("key", AuthKind::Hmac).unwrap(),
+            Secret::Hmac(key) if key == "key"
+        ));
+        assert!(Secret::from_config("", AuthKind::Hmac).is_err());
+        assert!(Secret::from_config(&argon2_hash("pw"), AuthKind::Hmac).is_err());
+        assert!(Secret::from_config("$2b$04$abcdefghijklmnopqrstuu", AuthKind::Hmac).is_err());
+    }
+
+    #[test]
+    fn rejects_reused_token_values() {
+        let result = TokenRegistry::from_config("same", &[config("a", "same")], "");
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn authenticates_plain_tokens_by_digest() {
+        let tokens = registry(&[config("a", "tok-a"), config("b", "tok-b")]);
+        assert_eq!(tokens.authenticate("tok-a").await.unwrap().id, "a");
+        assert_eq!(tokens.authenticate("tok-b").await.unwrap().id, "b");
+        assert_eq!(tokens.authenticate("tok-c").await.unwrap_err(), "invalid or missing token");
+        assert!(tokens.authenticate("").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn password_becomes_default_admin_token() {
+        let tokens = TokenRegistry::from_config("adminpw", &[], "").unwrap();
+        let principal = tokens.authenticate("adminpw").await.unwrap();
+        assert_eq!(principal.id, "default");
+        assert!(principal.has(Scope::Admin));
+    }
+
+    #[tokio::test]
+    async fn uid_only_tokens_do_not_match_empty_token() {
+        let mut uid_only = config("local", "");
+        uid_only.unix_uids = vec![1000];
+        let tokens = registry(&[uid_only]);
+        assert!(tokens.authenticate("").await.is_err());
+        assert_eq!(tokens.authenticate_uid(1000).await.unwrap().id, "local");
+        assert!(tokens.authenticate_uid(1001).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn verifies_argon2_and_caches_the_result() {
+        let tokens = registry(&[config("hashed", &argon2_hash("secret-pw"))]);
+        assert!(tokens.authenticate("wrong-pw").await.is_err());
+        assert!(tokens.verified.read().await.is_empty());
+
+        assert_eq!(tokens.authenticate("secret-pw").await.unwrap().id, "hashed");
+        assert_eq!(
+            tokens.verified.read().await.get(&digest("secret-pw")).map(String::as_str),
+            Some("hashed")
+        );
+        // 第二次命中缓存，结果相同
+        assert_eq!(tokens.authenticate("secret-pw").await.unwrap().id, "hashed");
+    }
+
+    #[tokio::test]
+    async fn verifies_bcrypt() {
+        let tokens = registry(&[config("bcrypt", &bcrypt::hash("secret-pw", 4).unwrap())]);
+        assert!(tokens.authenticate("wrong-pw").await.is_err());
+        assert_eq!(tokens.authenticate("secret-pw").await.unwrap().id, "bcrypt");
+        assert!(tokens.verified.read().await.contains_key(&digest("secret-pw")));
+    }
+
+    #[tokio::test]
+    async fn rejects_expired_tokens() {
+        let mut expired = config("old", "tok-old");
+        expired.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        let tokens = registry(&[expired]);
+        assert_eq!(tokens.authenticate("tok-old").await.unwrap_err(), "token expired");
+    }
+
+    fn principal(scopes: &[Scope], devices: &[&str]) -> Principal {
+        Principal {
+            id: "agent".to_string(),
+            name: "agent".to_string(),
+            scopes: scopes.to_vec(),
+            devices: devices.iter().map(|d| d.to_string()).collect(),
+            rate_limit: None,
+            max_active_jobs: None,
+            allowed_networks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn scopes_are_checked_individually_and_admin_has_all() {
+        let agent = principal(&[Scope::Notify, Scope::JobsRead], &[]);
+        assert!(agent.has(Scope::Notify));
+        assert!(agent.has(Scope::JobsRead));
+        assert!(!agent.has(Scope::JobsWrite));
+        assert!(!agent.has(Scope::Schedule));
+        assert!(!agent.has(Scope::Admin));
+
+        let admin = principal(&[Scope::Admin], &[]);
+        for scope in [Scope::Notify, Scope::Schedule, Scope::JobsRead, Scope::JobsWrite] {
+            assert!(admin.has(scope));
+        }
+    }
+
+    #[test]
+    fn only_admin_sees_other_owners_jobs() {
+        let agent = principal(&[Scope::JobsRead], &[]);
+        assert!(agent.owns("agent"));
+        assert!(!agent.owns("other"));
+        assert_eq!(agent.owner_filter().as_deref(), Some("agent"));
+
+        let admin = principal(&[Scope::Admin], &[]);
+        assert!(admin.owns("other"));
+        assert_eq!(admin.owner_filter(), None);
+    }
+
+    #[test]
+    fn device_restrictions() {
+        let any = principal(&[Scope::Notify], &[]);
+        assert!(any.check_device(None).is_ok());
+        assert!(any.check_device(Some("ipad")).is_ok());
+
+        let restricted = principal(&[Scope::Notify], &["ipad"]);
+        assert!(restricted.check_device(Some("ipad")).is_ok());
+        let err = restricted.check_device(Some("iphone")).unwrap_err();
+        assert!(err.to_string().contains("not allowed to send to device iphone"), "{}", err);
+        // 未指定设备时为 default 设备，同样受限制
+        assert!(restricted.check_device(None).is_err());
+        assert!(principal(&[Scope::Notify], &["default"]).check_device(None).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_token_configs() {
+        let mut no_scopes = config("a", "tok-a");
+        no_scopes.scopes.clear();
+        assert!(TokenRegistry::from_config("", &[no_scopes], "").is_err());
+        assert!(TokenRegistry::from_config("", &[config("a", "x"), config("a", "y")], "").is_err());
+        assert!(TokenRegistry::from_config("", &[config("", "x")], "").is_err());
+    }
+
+    #[tokio::test]
+    async fn principal_carries_the_token_restrictions() {
+        let mut limited = config("limited", "tok-limited");
+        limited.devices = vec!["ipad".to_string()];
+        limited.scopes = vec![Scope::Notify, Scope::Schedule];
+        let tokens = registry(&[limited]);
+        let principal = tokens.authenticate("tok-limited").await.unwrap();
+        assert_eq!(principal.devices, ["ipad"]);
+        assert!(principal.has(Scope::Schedule));
+        assert!(!principal.has(Scope::JobsWrite));
+    }
+
+    #[tokio::test]
+    async fn rotation_invalidates_the_old_value() {
+        let tokens = registry(&[config("admin", "tok-admin")]);
+        let issued = tokens
+            .create(TokenRequest {
+                name: "agent".to_string(),
+                scopes: vec![Scope::Notify],
+                auth: AuthKind::Bearer,
+                expires_at: None,
+                devices: Vec::new(),
+                rate_limit: None,
+                max_active_jobs: None,
+                allowed_networks: Vec::new(),
+            })
+            .await
+            .unwrap();
+        assert!(tokens.authenticate(&issued.token).await.is_ok());
+        let rotated = tokens.rotate(&issued.info.id).await.unwrap();
+        assert!(tokens.authenticate(&issued.token).await.is_err());
+        assert_eq!(tokens.authenticate(&rotated.token).await.unwrap().id, issued.info.id);
+        assert!(tokens.rotate("admin").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn store_is_private_even_if_temp_file_exists() {
+        let dir = std::env::temp_dir().join(format!("tokens-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let store = dir.join("tokens.json");
+        // 上次写入中断留下的临时文件，权限过宽
+        let tmp = dir.join("tokens.json.tmp");
+        std::fs::write(&tmp, b"[]").unwrap();
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let tokens = TokenRegistry::from_config("", &[], store.to_str().unwrap()).unwrap();
+        let req = serde_json::from_value(serde_json::json!({
+            "name": "signer", "scopes": ["notify"], "auth": "hmac"
+        }))
+        .unwrap();
+        tokens.create(req).await.unwrap();
+
+        let mode = std::fs::metadata(&store).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
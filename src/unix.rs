@@ -0,0 +1,97 @@
+use axum::{extract::Request, Router};
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::Path;
+use std::time::Duration;
+use tokio::net::UnixListener;
+use tower::ServiceExt;
+use tracing::{debug, error};
+
+/// Unix socket 连接对端进程的 UID，由 serve 放入请求扩展
+#[derive(Debug, Clone, Copy)]
+pub struct PeerCred {
+    pub uid: u32,
+}
+
+/// 创建 Unix socket 并设置权限和属主；路径上残留的旧 socket 文件会被删除
+pub fn bind(path: &Path, mode: u32, owner: &str) -> anyhow::Result<UnixListener> {
+    if let Ok(meta) = fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(anyhow::anyhow!("{} exists and is not a socket", path.display()));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|e| anyhow::anyhow!("Failed to bind {}: {}", path.display(), e))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    if !owner.is_empty() {
+        let (uid, gid) = parse_owner(owner)?;
+        std::os::unix::fs::chown(path, uid, gid)
+            .map_err(|e| anyhow::anyhow!("Failed to chown {} to {}: {}", path.display(), owner, e))?;
+    }
+    Ok(listener)
+}
+
+/// 解析 "用户[:组]"，可以是名称或数字 ID
+fn parse_owner(owner: &str) -> anyhow::Result<(Option<u32>, Option<u32>)> {
+    let (user, group) = match owner.split_once(':') {
+        Some((user, group)) => (user, Some(group)),
+        None => (owner, None),
+    };
+    let uid = (!user.is_empty())
+        .then(|| lookup_id("/etc/passwd", user))
+        .transpose()?;
+    let gid = group
+        .filter(|group| !group.is_empty())
+        .map(|group| lookup_id("/etc/group", group))
+        .transpose()?;
+    Ok((uid, gid))
+}
+
+/// 数字直接使用，否则在 /etc/passwd 或 /etc/group 中按名称查找（第三列为 ID）
+fn lookup_id(file: &str, name: &str) -> anyhow::Result<u32> {
+    if let Ok(id) = name.parse() {
+        return Ok(id);
+    }
+    fs::read_to_string(file)?
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&name))
+        .and_then(|fields| fields.get(2)?.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("{} not found in {}", name, file))
+}
+
+/// 在 Unix socket 上提供与 TCP 相同的路由，每个请求附带对端凭据
+pub async fn serve(listener: UnixListener, app: Router) -> anyhow::Result<()> {
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // 与 axum::serve 一致，出错后稍等再接受连接（如文件描述符耗尽）
+                error!("Failed to accept unix socket connection: {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let peer = stream.peer_cred().ok().map(|cred| PeerCred { uid: cred.uid() });
+
+        let app = app.clone();
+        let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+            if let Some(peer) = peer {
+                request.extensions_mut().insert(peer);
+            }
+            app.clone().oneshot(request)
+        });
+        tokio::spawn(async move {
+            if let Err(e) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Unix socket connection closed: {}", e);
+            }
+        });
+    }
+}